@@ -1,46 +1,338 @@
 #[macro_use]
 extern crate tracing;
 
+use bon::Builder;
 use quick_error::quick_error;
 use rayon::ThreadPool;
-use tokio::sync::oneshot;
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Instant,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, oneshot};
 
 quick_error! {
     #[derive(Debug)]
     pub enum Error {
+        /// Pool has already been initialised and can't be configured anymore
+        AlreadyInitialised {
+            display("Pool has already been initialised")
+        }
+
+        /// Configuration of the pool is invalid
+        InvalidConfig(reason: &'static str) {
+            display("Invalid pool configuration: {reason}")
+        }
+
         Oneshot(err: oneshot::error::RecvError) {
             from()
         }
 
+        /// Pool is at capacity and configured to reject new tasks
+        Overloaded {
+            display("Pool is overloaded")
+        }
+
+        /// Task panicked
+        Panicked(message: String) {
+            display("Task panicked: {message}")
+        }
+
+        /// Failed to build the thread pool
+        ThreadPoolBuild(err: rayon::ThreadPoolBuildError) {
+            from()
+        }
+
         TokioJoin(err: tokio::task::JoinError) {
             from()
         }
     }
 }
 
+/// Configuration of a rayon-backed pool
+///
+/// Every unset value falls back to rayon's default
+#[derive(Builder, Clone, Debug, Default)]
+pub struct PoolConfig {
+    /// Amount of threads in the pool
+    num_threads: Option<usize>,
+
+    /// Prefix of the thread names (the threads are named `<prefix>-<index>`)
+    ///
+    /// Defaults to `blowocking-<pool name>`
+    #[builder(into)]
+    thread_name: Option<String>,
+
+    /// Stack size of each thread in bytes
+    stack_size: Option<usize>,
+
+    /// Maximum amount of tasks which can be queued or running at the same time
+    ///
+    /// Defaults to unbounded. Has to be at least 1, otherwise no task could ever run.
+    max_tasks: Option<usize>,
+
+    /// What to do with new tasks once the pool is at capacity
+    #[builder(default)]
+    overload_behaviour: OverloadBehaviour,
+}
+
+/// Behaviour of a bounded pool once it is at capacity
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverloadBehaviour {
+    /// Wait asynchronously until capacity is available
+    #[default]
+    Wait,
+
+    /// Fail fast with [`Error::Overloaded`]
+    Reject,
+}
+
+/// Snapshot of the current load of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Amount of tasks waiting for a thread
+    pub queued: usize,
+
+    /// Amount of tasks currently running
+    pub active: usize,
+}
+
+/// Rayon-backed pools
+///
+/// Each pool reports the following metrics through the [`metrics`] facade, labelled with the name of the pool:
+///
+/// - `blowocking_queued_tasks` (gauge): amount of tasks waiting for a thread
+/// - `blowocking_active_tasks` (gauge): amount of tasks currently running
+/// - `blowocking_task_wait_seconds` (histogram): time tasks spent waiting for a thread
+/// - `blowocking_task_duration_seconds` (histogram): time tasks spent running
+/// - `blowocking_rejected_tasks` (counter): amount of tasks rejected due to the pool being at capacity
+/// - `blowocking_cancelled_tasks` (counter): amount of tasks skipped because the caller stopped waiting for them before they started
+/// - `blowocking_panicked_tasks` (counter): amount of tasks which panicked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pool {
+    /// Pool backing [`cpu`]
+    Cpu,
+
+    /// Pool backing [`crypto`]
+    Crypto,
+}
+
+/// Limit of a bounded pool
+struct Limit {
+    semaphore: Arc<Semaphore>,
+    overload_behaviour: OverloadBehaviour,
+}
+
+impl Limit {
+    #[inline]
+    async fn acquire(&self, pool: Pool) -> Result<OwnedSemaphorePermit, Error> {
+        match self.overload_behaviour {
+            OverloadBehaviour::Wait => Ok(Arc::clone(&self.semaphore)
+                .acquire_owned()
+                .await
+                .expect("[Bug] Semaphore closed")),
+            OverloadBehaviour::Reject => {
+                Arc::clone(&self.semaphore)
+                    .try_acquire_owned()
+                    .map_err(|_| {
+                        metrics::counter!("blowocking_rejected_tasks", "pool" => pool.name())
+                            .increment(1);
+                        Error::Overloaded
+                    })
+            }
+        }
+    }
+}
+
+struct Inner {
+    pool: ThreadPool,
+    limit: Option<Limit>,
+}
+
+struct PoolState {
+    inner: OnceLock<Inner>,
+    queued: AtomicUsize,
+    active: AtomicUsize,
+}
+
+impl PoolState {
+    const fn new() -> Self {
+        Self {
+            inner: OnceLock::new(),
+            queued: AtomicUsize::new(0),
+            active: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Pool {
+    /// Configure the pool
+    ///
+    /// This has to be called before the pool is used for the first time, otherwise it was already initialised with the default configuration
+    #[cfg_attr(not(coverage), instrument(skip_all, fields(pool = self.name())))]
+    pub fn configure(self, config: PoolConfig) -> Result<(), Error> {
+        if config.max_tasks == Some(0) {
+            return Err(Error::InvalidConfig("max_tasks has to be at least 1"));
+        }
+
+        if self.state().inner.get().is_some() {
+            return Err(Error::AlreadyInitialised);
+        }
+
+        let inner = self.build(config)?;
+        self.state()
+            .inner
+            .set(inner)
+            .map_err(|_| Error::AlreadyInitialised)
+    }
+
+    /// Current load of the pool
+    #[inline]
+    #[must_use]
+    pub fn stats(self) -> PoolStats {
+        let state = self.state();
+
+        PoolStats {
+            queued: state.queued.load(Ordering::Relaxed),
+            active: state.active.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Name of the pool as used in thread names and metric labels
+    #[inline]
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Crypto => "crypto",
+        }
+    }
+
+    fn build(self, config: PoolConfig) -> Result<Inner, Error> {
+        let thread_name = config
+            .thread_name
+            .unwrap_or_else(|| format!("blowocking-{}", self.name()));
+
+        let mut builder = rayon::ThreadPoolBuilder::new()
+            .thread_name(move |index| format!("{thread_name}-{index}"));
+
+        if let Some(num_threads) = config.num_threads {
+            builder = builder.num_threads(num_threads);
+        }
+        if let Some(stack_size) = config.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        let limit = config.max_tasks.map(|max_tasks| Limit {
+            semaphore: Arc::new(Semaphore::new(max_tasks)),
+            overload_behaviour: config.overload_behaviour,
+        });
+
+        Ok(Inner {
+            pool: builder.build()?,
+            limit,
+        })
+    }
+
+    #[inline]
+    fn state(self) -> &'static PoolState {
+        static CPU: PoolState = PoolState::new();
+        static CRYPTO: PoolState = PoolState::new();
+
+        match self {
+            Self::Cpu => &CPU,
+            Self::Crypto => &CRYPTO,
+        }
+    }
+
+    #[inline]
+    fn inner(self) -> &'static Inner {
+        self.state().inner.get_or_init(|| {
+            self.build(PoolConfig::default())
+                .expect("Failed to build rayon threadpool")
+        })
+    }
+}
+
+/// Extract the message from a panic payload
 #[inline]
-async fn run_blocking<F, O>(pool: &ThreadPool, func: F) -> Result<O, Error>
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[inline]
+async fn run_blocking<F, O>(pool: Pool, func: F) -> Result<O, Error>
 where
     F: FnOnce() -> O + Send + 'static,
     O: Send + 'static,
 {
+    let inner = pool.inner();
+    let permit = match inner.limit {
+        Some(ref limit) => Some(limit.acquire(pool).await?),
+        None => None,
+    };
+
     let (sender, receiver) = oneshot::channel();
+    let state = pool.state();
+    let name = pool.name();
+
+    state.queued.fetch_add(1, Ordering::Relaxed);
+    metrics::gauge!("blowocking_queued_tasks", "pool" => name).increment(1);
+
+    let queued_at = Instant::now();
+    inner.pool.spawn(move || {
+        state.queued.fetch_sub(1, Ordering::Relaxed);
+        metrics::gauge!("blowocking_queued_tasks", "pool" => name).decrement(1);
+
+        // Nobody is waiting for the result anymore, so don't bother computing it
+        if sender.is_closed() {
+            debug!("Skipping cancelled task");
+            metrics::counter!("blowocking_cancelled_tasks", "pool" => name).increment(1);
+            return;
+        }
+
+        metrics::histogram!("blowocking_task_wait_seconds", "pool" => name)
+            .record(queued_at.elapsed());
+
+        state.active.fetch_add(1, Ordering::Relaxed);
+        metrics::gauge!("blowocking_active_tasks", "pool" => name).increment(1);
 
-    pool.spawn(move || {
         let _span = info_span!("rayon-worker", id = %rayon::current_thread_index().unwrap());
 
-        let out = func();
+        let started_at = Instant::now();
+        let out = panic::catch_unwind(AssertUnwindSafe(func)).map_err(|payload| {
+            metrics::counter!("blowocking_panicked_tasks", "pool" => name).increment(1);
+            Error::Panicked(panic_message(&*payload))
+        });
+
+        metrics::histogram!("blowocking_task_duration_seconds", "pool" => name)
+            .record(started_at.elapsed());
+        state.active.fetch_sub(1, Ordering::Relaxed);
+        metrics::gauge!("blowocking_active_tasks", "pool" => name).decrement(1);
+
+        // Release the capacity before handing back the result so the caller can immediately schedule new work
+        drop(permit);
 
         if sender.send(out).is_err() {
             debug!("Failed to send back value from rayon threadpool");
         }
     });
 
-    receiver.await.map_err(Error::from)
+    receiver.await?
 }
 
 macro_rules! define_rayon_pool {
-    (name: $name:ident, description: $description:literal) => {
+    (name: $name:ident, pool: $pool:ident, description: $description:literal) => {
         #[inline]
         #[doc = $description]
         pub async fn $name<F, O>(func: F) -> Result<O, Error>
@@ -48,26 +340,20 @@ macro_rules! define_rayon_pool {
             F: FnOnce() -> O + Send + 'static,
             O: Send + 'static,
         {
-            use std::sync::LazyLock;
-
-            static POOL: LazyLock<rayon::ThreadPool> = LazyLock::new(|| {
-                rayon::ThreadPoolBuilder::new()
-                    .build()
-                    .expect("Failed to build rayon threadpool")
-            });
-
-            $crate::run_blocking(&POOL, func).await
+            $crate::run_blocking($crate::Pool::$pool, func).await
         }
     };
 }
 
 define_rayon_pool! {
     name: cpu,
+    pool: Cpu,
     description: "Spawn general-purpose CPU bound work (image conversion, compression, etc.)"
 }
 
 define_rayon_pool! {
     name: crypto,
+    pool: Crypto,
     description: "Spawn cryptography-related work (signature creation/verification, password hashing, etc)"
 }
 
@@ -78,5 +364,11 @@ where
     F: FnOnce() -> O + Send + 'static,
     O: Send + 'static,
 {
-    tokio::task::spawn_blocking(func).await.map_err(Error::from)
+    tokio::task::spawn_blocking(func).await.map_err(|err| {
+        if err.is_panic() {
+            Error::Panicked(panic_message(&*err.into_panic()))
+        } else {
+            Error::from(err)
+        }
+    })
 }
@@ -0,0 +1,58 @@
+use blowocking::{Error, OverloadBehaviour, Pool, PoolConfig};
+use std::sync::mpsc;
+
+#[tokio::test]
+async fn reject() {
+    Pool::Cpu
+        .configure(
+            PoolConfig::builder()
+                .max_tasks(1)
+                .overload_behaviour(OverloadBehaviour::Reject)
+                .build(),
+        )
+        .unwrap();
+
+    let (sender, receiver) = mpsc::channel::<()>();
+    let blocked = tokio::spawn(blowocking::cpu(move || receiver.recv()));
+    tokio::task::yield_now().await;
+
+    assert!(matches!(
+        blowocking::cpu(|| ()).await,
+        Err(Error::Overloaded)
+    ));
+
+    sender.send(()).unwrap();
+    blocked.await.unwrap().unwrap().unwrap();
+
+    blowocking::cpu(|| ()).await.unwrap();
+}
+
+#[tokio::test]
+async fn wait() {
+    Pool::Crypto
+        .configure(
+            PoolConfig::builder()
+                .max_tasks(1)
+                .overload_behaviour(OverloadBehaviour::Wait)
+                .build(),
+        )
+        .unwrap();
+
+    let (sender, receiver) = mpsc::channel::<()>();
+    let blocked = tokio::spawn(blowocking::crypto(move || receiver.recv()));
+    tokio::task::yield_now().await;
+
+    let waiting = tokio::spawn(blowocking::crypto(|| ()));
+    tokio::task::yield_now().await;
+    assert!(!waiting.is_finished());
+
+    sender.send(()).unwrap();
+    blocked.await.unwrap().unwrap().unwrap();
+    waiting.await.unwrap().unwrap();
+}
+
+#[test]
+fn zero_capacity() {
+    let result = Pool::Cpu.configure(PoolConfig::builder().max_tasks(0).build());
+    assert!(matches!(result, Err(Error::InvalidConfig(..))));
+}
@@ -0,0 +1,45 @@
+use blowocking::{Error, Pool, PoolConfig};
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+    mpsc,
+};
+
+#[tokio::test]
+async fn panic_is_caught() {
+    let result = blowocking::cpu(|| panic!("oh no")).await;
+    assert!(matches!(result, Err(Error::Panicked(message)) if message == "oh no"));
+
+    let result = blowocking::io(|| panic!("{} no", "oh")).await;
+    assert!(matches!(result, Err(Error::Panicked(message)) if message == "oh no"));
+
+    // The pool is still usable afterwards
+    assert_eq!(blowocking::cpu(|| 1 + 1).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn cancelled_task_is_skipped() {
+    Pool::Crypto
+        .configure(PoolConfig::builder().num_threads(1).build())
+        .unwrap();
+
+    let (sender, receiver) = mpsc::channel::<()>();
+    let blocked = tokio::spawn(blowocking::crypto(move || receiver.recv()));
+
+    let ran = Arc::new(AtomicBool::new(false));
+    let cancelled = tokio::spawn(blowocking::crypto({
+        let ran = Arc::clone(&ran);
+        move || ran.store(true, Ordering::Release)
+    }));
+    tokio::task::yield_now().await;
+
+    cancelled.abort();
+    assert!(cancelled.await.unwrap_err().is_cancelled());
+
+    sender.send(()).unwrap();
+    blocked.await.unwrap().unwrap().unwrap();
+
+    // The pool only has a single thread, so once this task ran, the cancelled task has been dequeued
+    blowocking::crypto(|| ()).await.unwrap();
+    assert!(!ran.load(Ordering::Acquire));
+}
@@ -0,0 +1,44 @@
+use blowocking::{Error, Pool, PoolConfig, PoolStats};
+use std::thread;
+
+#[tokio::test]
+async fn configure_before_use() {
+    Pool::Cpu
+        .configure(
+            PoolConfig::builder()
+                .num_threads(1)
+                .thread_name("test-cpu")
+                .build(),
+        )
+        .unwrap();
+
+    let thread_name = blowocking::cpu(|| thread::current().name().map(ToOwned::to_owned))
+        .await
+        .unwrap();
+    assert_eq!(thread_name.as_deref(), Some("test-cpu-0"));
+
+    assert!(matches!(
+        Pool::Cpu.configure(PoolConfig::default()),
+        Err(Error::AlreadyInitialised)
+    ));
+}
+
+#[tokio::test]
+async fn stats() {
+    let stats = blowocking::crypto(|| Pool::Crypto.stats()).await.unwrap();
+    assert_eq!(
+        stats,
+        PoolStats {
+            queued: 0,
+            active: 1
+        }
+    );
+
+    assert_eq!(
+        Pool::Crypto.stats(),
+        PoolStats {
+            queued: 0,
+            active: 0
+        }
+    );
+}
@@ -6,7 +6,7 @@
 //! ## Note
 //!
 //! The only supported HTTP methods for our hardening checks and the [`easy`] module are GET and POST.
-//! This is enough for the intended purpose of this library which is to allow for ActivityPub federation.
+//! This is enough for the intended purpose of this library which is to allow for `ActivityPub` federation.
 //! If you need more methods, feel free to open an issue.
 //!
 
@@ -8,10 +8,10 @@ use std::{
 use thiserror::Error;
 
 /// 1 minute
-const CLOCK_SKEW_ADJUSTMENT: Duration = Duration::from_secs(60);
+const CLOCK_SKEW_ADJUSTMENT: Duration = Duration::from_mins(1);
 
 /// 15 minutes
-const MAX_ACCEPTED_SIGNATURE_AGE: Duration = Duration::from_secs(15 * 60);
+const MAX_ACCEPTED_SIGNATURE_AGE: Duration = Duration::from_mins(15);
 
 const REQUIRED_GET_HEADERS: &[&str] = &["host"];
 const REQUIRED_POST_HEADERS: &[&str] = &["host", "content-type", "digest"];
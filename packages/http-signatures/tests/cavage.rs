@@ -1,3 +1,5 @@
+#![allow(unreachable_code, clippy::duration_suboptimal_units)]
+
 use const_oid::db::rfc5912::RSA_ENCRYPTION;
 use http_signatures::BoxError;
 use pkcs8::{
@@ -6,7 +8,10 @@ use pkcs8::{
     spki::AlgorithmIdentifier,
 };
 use scoped_futures::ScopedFutureExt;
-use std::{future, time::Duration};
+use std::{
+    future,
+    time::{Duration, SystemTime},
+};
 use tick_tock_mock::DeltaDirection;
 
 mod data;
@@ -84,3 +89,25 @@ async fn easy_expires() {
     .await
     .unwrap_err();
 }
+
+#[tokio::test]
+async fn easy_golden() {
+    let clock =
+        tick_tock_mock::Clock::frozen(SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500));
+    let _guard = clock.enter();
+
+    let req = self::data::get_request();
+    let signed_request =
+        http_signatures::cavage::easy::sign(req, "Test", &self::data::get_pkcs8_private_key())
+            .await
+            .unwrap();
+
+    assert_eq!(
+        signed_request.headers()["date"],
+        "Sun, 05 Jan 2014 21:31:40 GMT"
+    );
+    assert_eq!(
+        signed_request.headers()["signature"],
+        r#"keyId="Test",headers="host date content-type digest",signature="hIaNhofxGkl2q7FmXU+YHuUBsH4XGoL3UeARX9Lv8WNQcl+PAoEqgdpdn14c0QiiUul1qfVKXDDIOIk9xTBzwoVXNXb/k7O5iFxl2rmt+HuxjNe8s1NLe1rNEhIjcllkdEDUWIryBbkqO8J9ebqL40nfzauBnEBUqV6rcVAx+zHymzzZPuFhkb05aJjf6yeJ7l9qvr2bhlekSW7asex+G30yIVw6F1O6iMH514We+xhV0yX4HocL6KEAEP/7BSRzr1jK+PspRxmza5lHC2DIlZzTkHmDpNWHJ1CtyOnjWQeLHjUG8NoVBaWw0mfgjbjsRZSGvYZsVbwIWVYZUmmHOg==""#
+    );
+}
@@ -10,6 +10,11 @@ quick_error! {
     /// Error while decoding the manifest from a WASM module
     #[derive(Debug)]
     pub enum DecodeError {
+        /// The module contains a section multiple times
+        DuplicateSection(name: &'static str) {
+            display("module contains multiple \"{name}\" sections")
+        }
+
         /// Parsing of the JSON manifest failed
         Parse(err: serde_json::Error) {
             from()
@@ -22,32 +27,78 @@ quick_error! {
     }
 }
 
+/// Find all custom sections with the given name
+///
+/// Returns tuples consisting of the section data and the range of the section (including its type ID and length), in the order they appear in the module.
+pub(crate) fn custom_sections<'a>(
+    module: &'a [u8],
+    name: &str,
+) -> Result<Vec<(&'a [u8], SectionRange)>, DecodeError> {
+    let mut sections = Vec::new();
+
+    for payload in wasmparser::Parser::new(0).parse_all(module) {
+        let Payload::CustomSection(reader) = payload? else {
+            // Section we don't care about. Skip.
+            continue;
+        };
+
+        if reader.name() != name {
+            continue;
+        }
+
+        // Check the size of the LEB128 encoded integer
+        let length_size =
+            leb128::write::unsigned(&mut io::sink(), reader.range().len() as u64).unwrap();
+        let start_offset = 1 + length_size; // 1 byte for the section identifier, N bytes for the length of the section
+
+        let mut section_range = reader.range();
+        section_range.start -= start_offset;
+
+        sections.push((reader.data(), section_range));
+    }
+
+    Ok(sections)
+}
+
+/// Find the custom section with the given name
+///
+/// If it was found a tuple consisting of the section data and the range of the section (including its type ID and length) is returned.
+/// Errors if the module contains the section multiple times, since it would be ambiguous which one to use.
+pub(crate) fn custom_section<'a>(
+    module: &'a [u8],
+    name: &'static str,
+) -> Result<Option<(&'a [u8], SectionRange)>, DecodeError> {
+    let mut sections = custom_sections(module, name)?;
+    if sections.len() > 1 {
+        return Err(DecodeError::DuplicateSection(name));
+    }
+
+    Ok(sections.pop())
+}
+
+/// Find the ranges of all manifest sections (including their type ID and length) in a WASM module
+///
+/// Unlike [`decode`], this doesn't fail if the module contains multiple manifest sections.
+/// It can be used to strip all of them from the module.
+pub fn manifest_sections(module: &[u8]) -> Result<Vec<SectionRange>, DecodeError> {
+    let sections = custom_sections(module, SECTION_NAME)?
+        .into_iter()
+        .map(|(_data, section_range)| section_range)
+        .collect();
+
+    Ok(sections)
+}
+
 /// Decode a manifest from a WASM module
 ///
 /// If it was found a tuple consisting of the manifest and the custom section (including its type ID and length) is returned.
+/// Errors if the module contains multiple manifest sections.
 pub fn decode(module: &[u8]) -> Result<Option<(Manifest<'_>, SectionRange)>, DecodeError> {
-    let mut sections = wasmparser::Parser::new(0).parse_all(module);
-    let payload = loop {
-        match sections.next().transpose()? {
-            Some(Payload::CustomSection(reader)) if reader.name() == SECTION_NAME => {
-                break reader;
-            }
-            Some(..) => {
-                // Section we don't care about. Skip.
-            }
-            None => return Ok(None),
-        }
+    let Some((data, section_range)) = custom_section(module, SECTION_NAME)? else {
+        return Ok(None);
     };
 
-    // Check the size of the LEB128 encoded integer
-    let length_size =
-        leb128::write::unsigned(&mut io::sink(), payload.data().len() as u64).unwrap();
-    let start_offset = 1 + length_size; // 1 byte for the section identifier, N bytes for the length of the section
-
-    let mut section_range = payload.range();
-    section_range.start -= start_offset;
-
-    let manifest = serde_json::from_slice(payload.data())?;
+    let manifest = serde_json::from_slice(data)?;
 
     Ok(Some((manifest, section_range)))
 }
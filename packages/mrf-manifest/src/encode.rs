@@ -2,19 +2,23 @@ use crate::{Manifest, SECTION_NAME};
 use std::borrow::Cow;
 use wasm_encoder::{ComponentSection, CustomSection};
 
-/// Encode a manifest into its proper WASM custom section representation.
-///
-/// The manifest is encoded in canonical JSON.
-/// The emitted byte vector can directly be appended to a WASM component
-pub fn encode(manifest: &Manifest<'_>) -> Result<Vec<u8>, serde_json::Error> {
-    let canonical_manifest = crate::serialise(manifest)?;
+/// Encode arbitrary data into a WASM custom section with the given name
+pub(crate) fn custom_section(name: &str, data: Vec<u8>) -> Vec<u8> {
     let custom_section = CustomSection {
-        name: Cow::Borrowed(SECTION_NAME),
-        data: Cow::Owned(canonical_manifest),
+        name: Cow::Borrowed(name),
+        data: Cow::Owned(data),
     };
 
     let mut buf = Vec::new();
     custom_section.append_to_component(&mut buf);
+    buf
+}
 
-    Ok(buf)
+/// Encode a manifest into its proper WASM custom section representation.
+///
+/// The manifest is encoded in canonical JSON.
+/// The emitted byte vector can directly be appended to a WASM component
+pub fn encode(manifest: &Manifest<'_>) -> Result<Vec<u8>, serde_json::Error> {
+    let canonical_manifest = crate::serialise(manifest)?;
+    Ok(custom_section(SECTION_NAME, canonical_manifest))
 }
@@ -10,16 +10,23 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     ops::{Deref, DerefMut},
 };
 
 #[cfg(feature = "decode")]
-pub use self::decode::{DecodeError, SectionRange, decode};
+pub use self::decode::{DecodeError, SectionRange, decode, manifest_sections};
 #[cfg(feature = "encode")]
 pub use self::encode::encode;
 #[cfg(feature = "serialise")]
 pub use self::serialise::serialise;
+#[cfg(feature = "signature")]
+pub use self::signature::{
+    ModuleSignature, SIGNATURE_SECTION_NAME, SignatureAlgorithm, SignatureError, TrustStore,
+    TrustedKey, decode_signature, sign,
+};
+#[cfg(feature = "validate")]
+pub use self::validate::{ConfigError, ConfigViolation, ConfigViolations, validate_config};
 
 #[cfg(feature = "decode")]
 mod decode;
@@ -27,6 +34,10 @@ mod decode;
 mod encode;
 #[cfg(feature = "serialise")]
 mod serialise;
+#[cfg(feature = "signature")]
+mod signature;
+#[cfg(feature = "validate")]
+mod validate;
 
 /// Name of the section the manifest has to be encoded to
 pub const SECTION_NAME: &str = "manifest-v0";
@@ -90,6 +101,131 @@ impl<'a> From<BTreeSet<Cow<'a, str>>> for ActivitySet<'a> {
     }
 }
 
+/// Per-activity-type direction filters intended for use with the `activityTypes` field of [`ManifestV2`]
+///
+/// The key `*` matches all types not listed explicitly
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ActivityFilters<'a>(#[serde(borrow)] pub BTreeMap<Cow<'a, str>, DirectionFilter>);
+
+impl ActivityFilters<'_> {
+    /// Look up the direction filter for an activity type
+    ///
+    /// Falls back to the filter registered for `*` if the type isn't listed explicitly
+    #[must_use]
+    pub fn get(&self, activity_type: &str) -> Option<DirectionFilter> {
+        self.0
+            .get(activity_type)
+            .or_else(|| self.0.get("*"))
+            .copied()
+    }
+
+    /// Turn a borrowed version of `ActivityFilters` into a version with a `'static` lifetime
+    ///
+    /// This might allocate a bunch.
+    #[must_use]
+    pub fn to_owned(&self) -> ActivityFilters<'static> {
+        self.0
+            .iter()
+            .map(|(activity_type, filter)| (cow_to_static(activity_type.clone()), *filter))
+            .collect::<BTreeMap<Cow<'static, str>, DirectionFilter>>()
+            .into()
+    }
+}
+
+impl<'a> Deref for ActivityFilters<'a> {
+    type Target = BTreeMap<Cow<'a, str>, DirectionFilter>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ActivityFilters<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'a> From<ActivityFilters<'a>> for BTreeMap<Cow<'a, str>, DirectionFilter> {
+    fn from(value: ActivityFilters<'a>) -> Self {
+        value.0
+    }
+}
+
+impl<'a> From<BTreeMap<Cow<'a, str>, DirectionFilter>> for ActivityFilters<'a> {
+    fn from(value: BTreeMap<Cow<'a, str>, DirectionFilter>) -> Self {
+        Self(value)
+    }
+}
+
+impl<'a> From<ActivitySet<'a>> for ActivityFilters<'a> {
+    fn from(value: ActivitySet<'a>) -> Self {
+        value
+            .0
+            .into_iter()
+            .map(|activity_type| (activity_type, DirectionFilter::Both))
+            .collect::<BTreeMap<_, _>>()
+            .into()
+    }
+}
+
+/// Directions in which activities of a type are passed to the module
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DirectionFilter {
+    /// Only activities received from other servers
+    Incoming,
+
+    /// Only activities sent to other servers
+    Outgoing,
+
+    /// Activities in both directions
+    #[default]
+    Both,
+}
+
+impl DirectionFilter {
+    /// Does the filter let incoming activities through?
+    #[must_use]
+    pub fn incoming(self) -> bool {
+        matches!(self, Self::Incoming | Self::Both)
+    }
+
+    /// Does the filter let outgoing activities through?
+    #[must_use]
+    pub fn outgoing(self) -> bool {
+        matches!(self, Self::Outgoing | Self::Both)
+    }
+}
+
+/// Capability requested by an MRF module
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, Hash, JsonSchema, Ord, PartialEq, PartialOrd, Serialize,
+)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum Capability {
+    /// Access to the key-value storage
+    Storage,
+
+    /// Access to the logging interface
+    Logging,
+
+    /// Receive activities coming in from other servers
+    Incoming,
+
+    /// Receive activities going out to other servers
+    Outgoing,
+}
+
+impl Capability {
+    /// All capabilities
+    ///
+    /// Modules using a manifest version without capability declarations are assumed to request all of them
+    pub const ALL: [Self; 4] = [Self::Storage, Self::Logging, Self::Incoming, Self::Outgoing];
+}
+
 /// Version of the API used
 #[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -107,6 +243,10 @@ pub enum Manifest<'a> {
     /// Manifest v1
     #[serde(borrow)]
     V1(ManifestV1<'a>),
+
+    /// Manifest v2
+    #[serde(borrow)]
+    V2(ManifestV2<'a>),
 }
 
 impl Manifest<'_> {
@@ -117,6 +257,38 @@ impl Manifest<'_> {
     pub fn to_owned(&self) -> Manifest<'static> {
         match self {
             Self::V1(v1) => Manifest::V1(v1.to_owned()),
+            Self::V2(v2) => Manifest::V2(v2.to_owned()),
+        }
+    }
+}
+
+impl<'a> Manifest<'a> {
+    /// Version of the MRF API the module targets
+    #[must_use]
+    pub fn api_version(&self) -> ApiVersion {
+        match self {
+            Self::V1(v1) => v1.api_version,
+            Self::V2(v2) => v2.api_version,
+        }
+    }
+
+    /// JSON schema the configuration of the module has to match, if the module declares one
+    #[must_use]
+    pub fn config_schema(&self) -> Option<&schemars::Schema> {
+        match self {
+            Self::V1(v1) => v1.config_schema.as_ref(),
+            Self::V2(v2) => v2.config_schema.as_ref(),
+        }
+    }
+
+    /// Upgrade the manifest to the latest manifest version
+    ///
+    /// This is lossless, see the `From` implementations on [`ManifestV2`] for how missing fields are filled
+    #[must_use]
+    pub fn upgrade(self) -> ManifestV2<'a> {
+        match self {
+            Self::V1(v1) => v1.into(),
+            Self::V2(v2) => v2,
         }
     }
 }
@@ -161,3 +333,104 @@ impl ManifestV1<'_> {
         }
     }
 }
+
+/// Manifest v2
+///
+/// Additionally to the information in v1, modules declare metadata and the capabilities they request.
+/// This enables admins to know what a module wants before enabling it.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestV2<'a> {
+    /// Version of the MRF API
+    pub api_version: ApiVersion,
+
+    /// Name of the MRF module
+    pub name: Cow<'a, str>,
+
+    /// Version of the MRF module
+    pub version: semver::Version,
+
+    /// Short description of what the MRF module does
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<Cow<'a, str>>,
+
+    /// Authors of the MRF module
+    #[serde(borrow, default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<Cow<'a, str>>,
+
+    /// SPDX license expression of the MRF module
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<Cow<'a, str>>,
+
+    /// URL of the homepage of the MRF module
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<Cow<'a, str>>,
+
+    /// Minimum version of the host the MRF module requires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_host_version: Option<semver::Version>,
+
+    /// Capabilities requested by the MRF module
+    pub capabilities: BTreeSet<Capability>,
+
+    /// Activity types passed to the MRF module, together with the directions they are passed in
+    ///
+    /// `*` matching all types
+    #[serde(borrow)]
+    pub activity_types: ActivityFilters<'a>,
+
+    /// JSON schema of the configuration passed to the module
+    ///
+    /// This is optional but can be used for automatically generating a configuration UI
+    pub config_schema: Option<schemars::Schema>,
+}
+
+impl ManifestV2<'_> {
+    /// Does the module request the capability?
+    #[must_use]
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// Turn a borrowed version of `ManifestV2` into a version with a `'static` lifetime
+    ///
+    /// This might allocate a bunch.
+    #[must_use]
+    pub fn to_owned(&self) -> ManifestV2<'static> {
+        ManifestV2 {
+            api_version: self.api_version,
+            name: cow_to_static(self.name.clone()),
+            version: self.version.clone(),
+            description: self.description.clone().map(cow_to_static),
+            authors: self.authors.iter().cloned().map(cow_to_static).collect(),
+            license: self.license.clone().map(cow_to_static),
+            homepage: self.homepage.clone().map(cow_to_static),
+            min_host_version: self.min_host_version.clone(),
+            capabilities: self.capabilities.clone(),
+            activity_types: self.activity_types.to_owned(),
+            config_schema: self.config_schema.clone(),
+        }
+    }
+}
+
+/// Upgrade a v1 manifest
+///
+/// Version 1 had no way to restrict the module, so the module requests all capabilities
+/// and receives its activity types in both directions
+impl<'a> From<ManifestV1<'a>> for ManifestV2<'a> {
+    fn from(value: ManifestV1<'a>) -> Self {
+        Self {
+            api_version: value.api_version,
+            name: value.name,
+            version: value.version,
+            description: None,
+            authors: Vec::new(),
+            license: None,
+            homepage: None,
+            min_host_version: None,
+            capabilities: Capability::ALL.into(),
+            activity_types: value.activity_types.into(),
+            config_schema: value.config_schema,
+        }
+    }
+}
@@ -1,12 +1,38 @@
 use crate::Manifest;
 use fast_cjson::CanonicalFormatter;
 use serde::Serialize;
+use std::io::Write;
 
-/// Serialise a manifest into its canonical JSON representation
-pub fn serialise(manifest: &Manifest<'_>) -> Result<Vec<u8>, serde_json::Error> {
+/// Serialise any value into its canonical JSON representation
+///
+/// Canonical JSON only escapes quotes and backslashes, which leaves control characters in strings unescaped.
+/// Since that isn't valid JSON, they are escaped as `\u00XX` afterwards.
+/// Outside of strings, canonical JSON doesn't contain any whitespace, so every control character belongs to a string.
+pub(crate) fn to_canonical<T>(value: &T) -> Result<Vec<u8>, serde_json::Error>
+where
+    T: Serialize + ?Sized,
+{
     let mut buf = Vec::new();
     let mut ser = serde_json::Serializer::with_formatter(&mut buf, CanonicalFormatter::new());
-    manifest.serialize(&mut ser)?;
+    value.serialize(&mut ser)?;
+
+    if !buf.iter().any(|&byte| byte < 0x20) {
+        return Ok(buf);
+    }
+
+    let mut escaped = Vec::with_capacity(buf.len() + 16);
+    for byte in buf {
+        if byte < 0x20 {
+            write!(escaped, "\\u{byte:04x}").unwrap();
+        } else {
+            escaped.push(byte);
+        }
+    }
 
-    Ok(buf)
+    Ok(escaped)
+}
+
+/// Serialise a manifest into its canonical JSON representation
+pub fn serialise(manifest: &Manifest<'_>) -> Result<Vec<u8>, serde_json::Error> {
+    to_canonical(manifest)
 }
@@ -0,0 +1,210 @@
+use crate::{DecodeError, SectionRange, decode::custom_section, encode, serialise::to_canonical};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use http_signatures::{
+    crypto::{self, VerifyError},
+    ring::signature::{ED25519, Ed25519KeyPair, UnparsedPublicKey, VerificationAlgorithm},
+};
+use quick_error::quick_error;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Name of the section the signature has to be encoded to
+pub const SIGNATURE_SECTION_NAME: &str = "manifest-signature-v0";
+
+/// DER prefix of an Ed25519 public key in its SPKI form, followed by the 32 bytes of the key
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Length of an Ed25519 public key
+const ED25519_KEY_LENGTH: usize = 32;
+
+quick_error! {
+    /// Error while signing or verifying a module
+    #[derive(Debug)]
+    pub enum SignatureError {
+        /// Decoding the Base64-encoded public key failed
+        Base64(err: base64::DecodeError) {
+            from()
+        }
+
+        /// Decoding the module failed
+        Decode(err: DecodeError) {
+            from()
+        }
+
+        /// Public key isn't an Ed25519 key in its SPKI DER form
+        Key {
+            display("invalid public key (has to be an Ed25519 key)")
+        }
+
+        /// Module doesn't contain a manifest
+        MissingManifest {
+            display("missing manifest in module")
+        }
+
+        /// Module doesn't contain a signature
+        MissingSignature {
+            display("missing signature in module")
+        }
+
+        /// Serialising the manifest or the signature failed
+        Serialise(err: serde_json::Error) {
+            from()
+        }
+
+        /// Key the module was signed with isn't in the trust store
+        UntrustedKey(key_id: String) {
+            display("module is signed with untrusted key \"{key_id}\"")
+        }
+
+        /// Signature doesn't match the module
+        Verify(err: VerifyError) {
+            display("signature verification failed")
+            from()
+        }
+    }
+}
+
+/// Algorithm the module was signed with
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum SignatureAlgorithm {
+    /// Ed25519
+    Ed25519,
+}
+
+/// Signature embedded into a module
+///
+/// It covers the module bytes (excluding the signature section itself) and the canonical representation of the manifest
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleSignature<'a> {
+    /// Algorithm the module was signed with
+    pub algorithm: SignatureAlgorithm,
+
+    /// ID of the key the module was signed with
+    #[serde(borrow)]
+    pub key_id: Cow<'a, str>,
+
+    /// Base64-encoded signature
+    #[serde(borrow)]
+    pub signature: Cow<'a, str>,
+}
+
+/// Key of a trusted publisher
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustedKey {
+    /// ID of the key, has to match the key ID embedded into the signature
+    pub id: String,
+
+    /// Base64-encoded Ed25519 public key in its SPKI DER form
+    ///
+    /// This is the body of the PEM-encoded public key
+    pub public_key: String,
+}
+
+/// Set of keys modules have to be signed with to be loaded
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TrustStore {
+    /// Keys of trusted publishers
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Verify that the module was signed by one of the trusted keys
+    ///
+    /// Returns the key the module was signed with
+    pub fn verify(&self, module: &[u8]) -> Result<&TrustedKey, SignatureError> {
+        let Some((signature, section_range)) = decode_signature(module)? else {
+            return Err(SignatureError::MissingSignature);
+        };
+
+        let trusted_key = self
+            .keys
+            .iter()
+            .find(|key| key.id == signature.key_id)
+            .ok_or_else(|| SignatureError::UntrustedKey(signature.key_id.clone().into_owned()))?;
+
+        let algorithm: &'static dyn VerificationAlgorithm = match signature.algorithm {
+            SignatureAlgorithm::Ed25519 => &ED25519,
+        };
+
+        // Parse the key ourselves instead of accepting whatever algorithm the key declares.
+        // Otherwise a trusted RSA key would silently make RSA signatures acceptable.
+        let public_key = BASE64_STANDARD.decode(&trusted_key.public_key)?;
+        let public_key = public_key
+            .strip_prefix(ED25519_SPKI_PREFIX)
+            .filter(|raw_key| raw_key.len() == ED25519_KEY_LENGTH)
+            .ok_or(SignatureError::Key)?;
+        let public_key = UnparsedPublicKey::new(algorithm, public_key);
+
+        let module = [&module[..section_range.start], &module[section_range.end..]].concat();
+        let message = signed_message(&module)?;
+        crypto::verify(&message, &signature.signature, &public_key)?;
+
+        Ok(trusted_key)
+    }
+}
+
+/// Construct the message the signature is calculated over
+///
+/// The message is prefixed with the section name for domain separation
+fn signed_message(module: &[u8]) -> Result<Vec<u8>, SignatureError> {
+    let Some((manifest, _section_range)) = crate::decode(module)? else {
+        return Err(SignatureError::MissingManifest);
+    };
+    let canonical_manifest = crate::serialise(&manifest)?;
+
+    let mut message = Vec::with_capacity(
+        SIGNATURE_SECTION_NAME.len() + 1 + 8 + module.len() + canonical_manifest.len(),
+    );
+    message.extend_from_slice(SIGNATURE_SECTION_NAME.as_bytes());
+    message.push(0);
+    message.extend_from_slice(&(module.len() as u64).to_le_bytes());
+    message.extend_from_slice(module);
+    message.extend_from_slice(&canonical_manifest);
+
+    Ok(message)
+}
+
+/// Decode the signature from a WASM module
+///
+/// If it was found a tuple consisting of the signature and the custom section (including its type ID and length) is returned.
+pub fn decode_signature(
+    module: &[u8],
+) -> Result<Option<(ModuleSignature<'_>, SectionRange)>, DecodeError> {
+    let Some((data, section_range)) = custom_section(module, SIGNATURE_SECTION_NAME)? else {
+        return Ok(None);
+    };
+
+    Ok(Some((serde_json::from_slice(data)?, section_range)))
+}
+
+/// Sign a module containing a manifest
+///
+/// An existing signature is replaced. The returned module has the signature section appended
+pub fn sign(module: &[u8], key_id: &str, key: &Ed25519KeyPair) -> Result<Vec<u8>, SignatureError> {
+    let mut module = match decode_signature(module)? {
+        Some((_signature, section_range)) => {
+            [&module[..section_range.start], &module[section_range.end..]].concat()
+        }
+        None => module.to_vec(),
+    };
+
+    let message = signed_message(&module)?;
+    let signature = ModuleSignature {
+        algorithm: SignatureAlgorithm::Ed25519,
+        key_id: Cow::Borrowed(key_id),
+        signature: Cow::Owned(crypto::sign(&message, key)),
+    };
+
+    module.extend(encode::custom_section(
+        SIGNATURE_SECTION_NAME,
+        to_canonical(&signature)?,
+    ));
+
+    Ok(module)
+}
@@ -0,0 +1,108 @@
+use crate::Manifest;
+use quick_error::quick_error;
+use serde::Serialize;
+use std::fmt;
+
+/// Single violation of the configuration schema
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigViolation {
+    /// JSON pointer to the offending value inside the configuration
+    ///
+    /// Empty if the root of the configuration is the offending value
+    pub path: String,
+
+    /// Human-readable description of the violation
+    pub message: String,
+}
+
+impl fmt::Display for ConfigViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, "{path}: {}", self.message)
+    }
+}
+
+/// List of violations, displayed one per line
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigViolations(pub Vec<ConfigViolation>);
+
+impl fmt::Display for ConfigViolations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, violation) in self.0.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{violation}")?;
+        }
+
+        Ok(())
+    }
+}
+
+quick_error! {
+    /// Error while validating a configuration against a schema
+    #[derive(Debug)]
+    pub enum ConfigError {
+        /// The configuration schema itself is invalid
+        InvalidSchema(message: String) {
+            display("invalid configuration schema: {message}")
+        }
+
+        /// The configuration couldn't be converted into a JSON value
+        Serialise(err: serde_json::Error) {
+            from()
+        }
+
+        /// The configuration doesn't match the schema
+        Violations(violations: ConfigViolations) {
+            display("configuration doesn't match the schema:\n{violations}")
+        }
+    }
+}
+
+/// Validate a configuration against a JSON schema
+///
+/// The configuration can be any serialisable value, such as a `serde_json::Value` or a `toml::Table`.
+/// All violations are collected and reported together with the path to the offending value.
+pub fn validate_config<T>(schema: &schemars::Schema, config: &T) -> Result<(), ConfigError>
+where
+    T: Serialize + ?Sized,
+{
+    let validator = jsonschema::validator_for(schema.as_value())
+        .map_err(|error| ConfigError::InvalidSchema(error.to_string()))?;
+    let config = serde_json::to_value(config)?;
+
+    let violations: Vec<ConfigViolation> = validator
+        .iter_errors(&config)
+        .map(|error| ConfigViolation {
+            path: error.instance_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigError::Violations(ConfigViolations(violations)))
+    }
+}
+
+impl Manifest<'_> {
+    /// Validate a configuration against the configuration schema of the manifest
+    ///
+    /// Succeeds unconditionally if the manifest doesn't declare a configuration schema
+    pub fn validate_config<T>(&self, config: &T) -> Result<(), ConfigError>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self.config_schema() {
+            Some(schema) => validate_config(schema, config),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![cfg(feature = "decode")]
 
-use mrf_manifest::{Manifest, SECTION_NAME};
+use mrf_manifest::{ActivitySet, Manifest, SECTION_NAME};
+use std::collections::BTreeSet;
 
 const MANIFEST: &str = include_str!("test-manifest.json");
 
@@ -18,3 +19,84 @@ fn decode_works() {
     assert_eq!(manifest, parsed_manifest);
     assert_eq!(section_range, 8..245);
 }
+
+#[cfg(feature = "encode")]
+#[test]
+fn decode_control_characters() {
+    let mut manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    let Manifest::V1(ref mut v1) = manifest else {
+        unreachable!();
+    };
+    v1.config_schema = Some(schemars::json_schema!({
+        "description": "Multiple\nlines\tand tabs",
+        "type": "object",
+    }));
+
+    let mut wasm_blob = wat::parse_str("(module)").unwrap();
+    wasm_blob.extend(mrf_manifest::encode(&manifest).unwrap());
+
+    let (parsed_manifest, _section_range) = mrf_manifest::decode(&wasm_blob).unwrap().unwrap();
+    assert_eq!(manifest, parsed_manifest);
+}
+
+#[cfg(feature = "encode")]
+#[test]
+fn decode_duplicate_sections() {
+    let manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    let section = mrf_manifest::encode(&manifest).unwrap();
+
+    let mut wasm_blob = wat::parse_str("(module)").unwrap();
+    wasm_blob.extend(&section);
+    wasm_blob.extend(&section);
+
+    let error = mrf_manifest::decode(&wasm_blob).unwrap_err();
+    assert!(matches!(
+        error,
+        mrf_manifest::DecodeError::DuplicateSection(SECTION_NAME)
+    ));
+
+    let section_ranges = mrf_manifest::manifest_sections(&wasm_blob).unwrap();
+    assert_eq!(
+        section_ranges,
+        [
+            8..8 + section.len(),
+            8 + section.len()..8 + 2 * section.len()
+        ]
+    );
+}
+
+#[cfg(feature = "encode")]
+#[test]
+fn decode_section_length_boundary() {
+    const DATA_LENGTH: usize = 120;
+
+    let mut manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    let Manifest::V1(ref mut v1) = manifest else {
+        unreachable!();
+    };
+
+    // Pad the name so the data fits into a one byte LEB128 length, but the data together with the section name doesn't
+    v1.activity_types = ActivitySet(BTreeSet::from(["Create".into()]));
+    v1.version = semver::Version::new(0, 0, 1);
+    v1.name = "".into();
+    let padding = DATA_LENGTH - mrf_manifest::serialise(&manifest).unwrap().len();
+    let Manifest::V1(ref mut v1) = manifest else {
+        unreachable!();
+    };
+    v1.name = "a".repeat(padding).into();
+    assert_eq!(
+        mrf_manifest::serialise(&manifest).unwrap().len(),
+        DATA_LENGTH
+    );
+
+    let mut wasm_blob = wat::parse_str("(module)").unwrap();
+    wasm_blob.extend(mrf_manifest::encode(&manifest).unwrap());
+
+    let (parsed_manifest, section_range) = mrf_manifest::decode(&wasm_blob).unwrap().unwrap();
+    assert_eq!(manifest, parsed_manifest);
+    assert_eq!(section_range, 8..wasm_blob.len());
+    assert_eq!(
+        mrf_manifest::manifest_sections(&wasm_blob).unwrap(),
+        vec![section_range]
+    );
+}
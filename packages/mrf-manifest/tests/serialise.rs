@@ -9,3 +9,30 @@ fn serialise_works() {
     let encoded_manifest_str = String::from_utf8(encoded_manifest).unwrap();
     insta::assert_snapshot!(encoded_manifest_str);
 }
+
+#[test]
+fn serialise_v2_works() {
+    let manifest = serde_json::from_str(include_str!("test-manifest-v2.json")).unwrap();
+    let encoded_manifest = mrf_manifest::serialise(&manifest).unwrap();
+    let encoded_manifest_str = String::from_utf8(encoded_manifest).unwrap();
+    insta::assert_snapshot!(encoded_manifest_str);
+}
+
+#[test]
+fn serialise_control_characters() {
+    let mut manifest: mrf_manifest::Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    let mrf_manifest::Manifest::V1(ref mut v1) = manifest else {
+        unreachable!();
+    };
+    v1.config_schema = Some(schemars::json_schema!({
+        "description": "Multiple\nlines\tand tabs",
+    }));
+
+    let encoded_manifest = mrf_manifest::serialise(&manifest).unwrap();
+    let encoded_manifest_str = String::from_utf8(encoded_manifest).unwrap();
+    assert!(encoded_manifest_str.contains(r#""Multiple\u000alines\u0009and tabs""#));
+
+    let parsed_manifest: mrf_manifest::Manifest<'_> =
+        serde_json::from_str(&encoded_manifest_str).unwrap();
+    assert_eq!(manifest, parsed_manifest);
+}
@@ -0,0 +1,171 @@
+#![cfg(feature = "signature")]
+
+use http_signatures::ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use mrf_manifest::{SIGNATURE_SECTION_NAME, SignatureError, TrustStore, TrustedKey};
+
+const MANIFEST: &str = include_str!("test-manifest.json");
+
+/// DER prefix of an Ed25519 public key in its SPKI form
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+fn key_pair() -> Ed25519KeyPair {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+}
+
+fn trusted_key(id: &str, key_pair: &Ed25519KeyPair) -> TrustedKey {
+    use base64::{Engine, prelude::BASE64_STANDARD};
+
+    let spki = [ED25519_SPKI_PREFIX, key_pair.public_key().as_ref()].concat();
+    TrustedKey {
+        id: id.into(),
+        public_key: BASE64_STANDARD.encode(spki),
+    }
+}
+
+fn module() -> Vec<u8> {
+    let manifest = serde_json::from_str(MANIFEST).unwrap();
+    let mut module = wat::parse_str("(module)").unwrap();
+    module.extend(mrf_manifest::encode(&manifest).unwrap());
+    module
+}
+
+#[test]
+fn sign_and_verify() {
+    let key_pair = key_pair();
+    let trust_store = TrustStore {
+        keys: vec![trusted_key("publisher", &key_pair)],
+    };
+
+    let signed = mrf_manifest::sign(&module(), "publisher", &key_pair).unwrap();
+    let (signature, _section_range) = mrf_manifest::decode_signature(&signed).unwrap().unwrap();
+    assert_eq!(signature.key_id, "publisher");
+
+    let key = trust_store.verify(&signed).unwrap();
+    assert_eq!(key.id, "publisher");
+
+    // Re-signing replaces the existing signature
+    let resigned = mrf_manifest::sign(&signed, "publisher", &key_pair).unwrap();
+    assert_eq!(resigned.len(), signed.len());
+    trust_store.verify(&resigned).unwrap();
+}
+
+#[test]
+fn tampering() {
+    let key_pair = key_pair();
+    let trust_store = TrustStore {
+        keys: vec![trusted_key("publisher", &key_pair)],
+    };
+
+    let mut signed = mrf_manifest::sign(&module(), "publisher", &key_pair).unwrap();
+    // Append an empty custom section called "foo"
+    signed.extend([0x00, 0x04, 0x03, b'f', b'o', b'o']);
+
+    assert!(matches!(
+        trust_store.verify(&signed),
+        Err(SignatureError::Verify(..))
+    ));
+}
+
+#[test]
+fn untrusted() {
+    let key_pair = key_pair();
+    let signed = mrf_manifest::sign(&module(), "publisher", &key_pair).unwrap();
+
+    let empty = TrustStore::default();
+    assert!(matches!(
+        empty.verify(&signed),
+        Err(SignatureError::UntrustedKey(..))
+    ));
+
+    let impostor = TrustStore {
+        keys: vec![trusted_key("publisher", &self::key_pair())],
+    };
+    assert!(matches!(
+        impostor.verify(&signed),
+        Err(SignatureError::Verify(..))
+    ));
+
+    assert!(matches!(
+        empty.verify(&module()),
+        Err(SignatureError::MissingSignature)
+    ));
+}
+
+/// Body of the PEM block with the given label
+fn pem_body(pem: &str, label: &str) -> Vec<u8> {
+    use base64::{Engine, prelude::BASE64_STANDARD};
+
+    let begin = format!("-----BEGIN {label}-----");
+    let end = format!("-----END {label}-----");
+    let body: String = pem
+        .lines()
+        .skip_while(|line| *line != begin)
+        .skip(1)
+        .take_while(|line| *line != end)
+        .collect();
+
+    BASE64_STANDARD.decode(body).unwrap()
+}
+
+/// Append a signature section to the module, bypassing `sign`
+fn append_signature(module: &[u8], algorithm: &str, key_id: &str, signature: &str) -> Vec<u8> {
+    let section = serde_json::json!({
+        "algorithm": algorithm,
+        "keyId": key_id,
+        "signature": signature,
+    });
+    let escaped_section = serde_json::to_string(&section.to_string()).unwrap();
+    let wat_src = format!(r#"( module ( @custom "{SIGNATURE_SECTION_NAME}" {escaped_section} ) )"#);
+
+    let signature_module = wat::parse_str(wat_src).unwrap();
+    // Skip the magic number and version of the second module, leaving only its custom section
+    [module, &signature_module[8..]].concat()
+}
+
+#[test]
+fn rsa_rejected() {
+    use base64::{Engine, prelude::BASE64_STANDARD};
+    use http_signatures::crypto;
+
+    const RSA_KEY: &str = include_str!("test-rsa-key.pem");
+
+    let private_key = crypto::parse::private_key(&pem_body(RSA_KEY, "PRIVATE KEY")).unwrap();
+    let trust_store = TrustStore {
+        keys: vec![TrustedKey {
+            id: "publisher".into(),
+            public_key: BASE64_STANDARD.encode(pem_body(RSA_KEY, "PUBLIC KEY")),
+        }],
+    };
+
+    // Same message `sign` calculates the signature over
+    let module = module();
+    let (manifest, _section_range) = mrf_manifest::decode(&module).unwrap().unwrap();
+    let mut message = SIGNATURE_SECTION_NAME.as_bytes().to_vec();
+    message.push(0);
+    message.extend_from_slice(&(module.len() as u64).to_le_bytes());
+    message.extend_from_slice(&module);
+    message.extend_from_slice(&mrf_manifest::serialise(&manifest).unwrap());
+    let signature = crypto::sign(&message, &private_key);
+
+    // The signature is valid, but the key isn't an Ed25519 one
+    let public_key = crypto::parse::public_key(&pem_body(RSA_KEY, "PUBLIC KEY")).unwrap();
+    crypto::verify(&message, &signature, &public_key).unwrap();
+
+    let signed = append_signature(&module, "ed25519", "publisher", &signature);
+    assert!(matches!(
+        trust_store.verify(&signed),
+        Err(SignatureError::Key)
+    ));
+
+    let signed = append_signature(&module, "rsaSha256", "publisher", &signature);
+    assert!(matches!(
+        trust_store.verify(&signed),
+        Err(SignatureError::Decode(..))
+    ));
+}
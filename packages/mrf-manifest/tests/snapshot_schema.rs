@@ -1,7 +1,13 @@
-use mrf_manifest::Manifest;
+use mrf_manifest::{Manifest, ManifestV2};
 
 #[test]
 fn json_schema() {
     let schema = schemars::schema_for!(Manifest<'_>);
     insta::assert_json_snapshot!(schema);
 }
+
+#[test]
+fn json_schema_v2() {
+    let schema = schemars::schema_for!(ManifestV2<'_>);
+    insta::assert_json_snapshot!(schema);
+}
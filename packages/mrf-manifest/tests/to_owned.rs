@@ -8,3 +8,11 @@ fn to_owned_works() {
     let owned_manifest: Manifest<'static> = borrowed_manifest.to_owned();
     assert_eq!(borrowed_manifest, owned_manifest);
 }
+
+#[test]
+fn to_owned_v2_works() {
+    let borrowed_manifest: Manifest<'_> =
+        serde_json::from_str(include_str!("test-manifest-v2.json")).unwrap();
+    let owned_manifest: Manifest<'static> = borrowed_manifest.to_owned();
+    assert_eq!(borrowed_manifest, owned_manifest);
+}
@@ -0,0 +1,59 @@
+use mrf_manifest::{Capability, DirectionFilter, Manifest};
+
+const MANIFEST_V1: &str = include_str!("test-manifest.json");
+const MANIFEST_V2: &str = include_str!("test-manifest-v2.json");
+
+#[test]
+fn upgrade_v1() {
+    let manifest: Manifest<'_> = serde_json::from_str(MANIFEST_V1).unwrap();
+    let Manifest::V1(ref v1) = manifest else {
+        panic!("unexpected manifest version");
+    };
+    let v1 = v1.clone();
+    let v2 = manifest.upgrade();
+
+    assert_eq!(v2.api_version, v1.api_version);
+    assert_eq!(v2.name, v1.name);
+    assert_eq!(v2.version, v1.version);
+    assert_eq!(v2.config_schema, v1.config_schema);
+    assert!(Capability::ALL.iter().all(|cap| v2.has_capability(*cap)));
+
+    assert_eq!(v2.activity_types.len(), v1.activity_types.len());
+    for activity_type in v1.activity_types.iter() {
+        assert_eq!(
+            v2.activity_types.get(activity_type),
+            Some(DirectionFilter::Both)
+        );
+    }
+}
+
+#[test]
+fn upgrade_v2() {
+    let manifest: Manifest<'_> = serde_json::from_str(MANIFEST_V2).unwrap();
+    let Manifest::V2(ref v2) = manifest else {
+        panic!("unexpected manifest version");
+    };
+    assert_eq!(*v2, manifest.clone().upgrade());
+}
+
+#[test]
+fn activity_filters() {
+    let Manifest::V2(manifest) = serde_json::from_str(MANIFEST_V2).unwrap() else {
+        panic!("unexpected manifest version");
+    };
+
+    let create = manifest.activity_types.get("Create").unwrap();
+    assert!(create.incoming());
+    assert!(!create.outgoing());
+
+    let delete = manifest.activity_types.get("Delete").unwrap();
+    assert!(delete.incoming());
+    assert!(delete.outgoing());
+
+    let like = manifest.activity_types.get("Like").unwrap();
+    assert!(!like.incoming());
+    assert!(like.outgoing());
+
+    assert!(manifest.has_capability(Capability::Logging));
+    assert!(!manifest.has_capability(Capability::Storage));
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "validate")]
+
+use mrf_manifest::{ConfigError, Manifest};
+use serde_json::json;
+
+const MANIFEST: &str = include_str!("test-manifest.json");
+
+fn schema() -> schemars::Schema {
+    serde_json::from_value(json!({
+        "type": "object",
+        "properties": {
+            "blockedDomains": {
+                "type": "array",
+                "items": { "type": "string" }
+            },
+            "threshold": {
+                "type": "integer",
+                "minimum": 0
+            }
+        },
+        "required": ["threshold"],
+        "additionalProperties": false
+    }))
+    .unwrap()
+}
+
+#[test]
+fn valid_config() {
+    let config = json!({
+        "blockedDomains": ["example.com"],
+        "threshold": 3,
+    });
+
+    mrf_manifest::validate_config(&schema(), &config).unwrap();
+}
+
+#[test]
+fn invalid_config() {
+    let config = json!({
+        "blockedDomains": ["example.com", 1],
+        "threshold": -1,
+    });
+
+    let error = mrf_manifest::validate_config(&schema(), &config).unwrap_err();
+    let ConfigError::Violations(violations) = error else {
+        panic!("unexpected error: {error}");
+    };
+
+    let mut paths: Vec<&str> = violations
+        .0
+        .iter()
+        .map(|violation| violation.path.as_str())
+        .collect();
+    paths.sort_unstable();
+
+    assert_eq!(paths, ["/blockedDomains/1", "/threshold"]);
+}
+
+#[test]
+fn missing_schema() {
+    let manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    manifest.validate_config(&json!("anything")).unwrap();
+
+    let Manifest::V1(manifest) = manifest else {
+        panic!("unexpected manifest version");
+    };
+    let manifest = Manifest::V1(mrf_manifest::ManifestV1 {
+        config_schema: Some(schema()),
+        ..manifest
+    });
+    assert!(manifest.validate_config(&json!({})).is_err());
+
+    let manifest = Manifest::V2(manifest.upgrade());
+    assert!(manifest.validate_config(&json!({})).is_err());
+}
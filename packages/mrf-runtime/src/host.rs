@@ -0,0 +1,87 @@
+//!
+//! Host-side implementation of the interfaces modules can import
+//!
+
+use crate::{
+    bindings::fep::mrf::{
+        clock::{self, Datetime},
+        key_value,
+        logging::{self, Level},
+    },
+    storage::KvStorage,
+};
+use std::{sync::Arc, time::SystemTime};
+use wasmtime::StoreLimits;
+
+/// Data available to the module during its execution
+///
+/// Capabilities the module didn't request in its manifest are unavailable
+pub struct Context {
+    pub limits: StoreLimits,
+    pub logging: bool,
+    pub module_name: Arc<str>,
+    pub storage: Option<Arc<dyn KvStorage>>,
+}
+
+impl Context {
+    #[inline]
+    fn storage(&self) -> Result<&dyn KvStorage, String> {
+        self.storage
+            .as_deref()
+            .ok_or_else(|| "storage capability not requested".into())
+    }
+}
+
+impl logging::Host for Context {
+    async fn log(&mut self, level: Level, message: String) {
+        if !self.logging {
+            return;
+        }
+
+        let module = &*self.module_name;
+
+        match level {
+            Level::Trace => trace!(%module, "{message}"),
+            Level::Debug => debug!(%module, "{message}"),
+            Level::Info => info!(%module, "{message}"),
+            Level::Warn => warn!(%module, "{message}"),
+            Level::Error => error!(%module, "{message}"),
+        }
+    }
+}
+
+impl key_value::Host for Context {
+    async fn get(&mut self, key: String) -> Result<Option<Vec<u8>>, String> {
+        self.storage()?
+            .get(&self.module_name, &key)
+            .await
+            .map_err(|error| error.to_string())
+    }
+
+    async fn set(&mut self, key: String, value: Vec<u8>) -> Result<(), String> {
+        self.storage()?
+            .set(&self.module_name, &key, value)
+            .await
+            .map_err(|error| error.to_string())
+    }
+
+    async fn remove(&mut self, key: String) -> Result<(), String> {
+        self.storage()?
+            .remove(&self.module_name, &key)
+            .await
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl clock::Host for Context {
+    async fn now(&mut self) -> Datetime {
+        let since_epoch = tick_tock_mock::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Datetime {
+            seconds: since_epoch.as_secs(),
+            nanoseconds: since_epoch.subsec_nanos(),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//!
+//! Runtime for executing WASM MRF modules
+//!
+//! Loads WASM components carrying an MRF manifest and passes activities through them
+//!
+
+#![deny(missing_docs)]
+
+#[macro_use]
+extern crate tracing;
+
+use self::{
+    bindings::{MrfV1Pre, Verdict},
+    host::Context,
+};
+use bon::{Builder, bon};
+use mrf_manifest::{ApiVersion, Capability, ManifestV2, TrustStore};
+use quick_error::quick_error;
+use std::{borrow::Cow, sync::Arc};
+use wasmtime::{
+    Config, Engine, Store, StoreLimitsBuilder, Trap,
+    component::{Component, HasSelf, Linker},
+};
+
+pub use self::{
+    bindings::Direction,
+    storage::{BoxError, KvStorage, MemoryStorage},
+};
+
+mod bindings {
+    #![allow(missing_docs)]
+
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "mrf-v1",
+        imports: {
+            default: async,
+        },
+        exports: {
+            default: async,
+        },
+    });
+}
+
+mod host;
+mod storage;
+
+/// WIT package defining the worlds MRF modules can target
+pub const WIT: &str = include_str!("../wit/mrf.wit");
+
+/// Version of the host, which the minimum host version requested by manifests is compared against
+pub const HOST_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Amount of fuel after which a running module yields back to the async runtime
+const FUEL_YIELD_INTERVAL: u64 = 10_000;
+
+quick_error! {
+    /// Error while loading or executing an MRF module
+    #[derive(Debug)]
+    pub enum Error {
+        /// Module doesn't conform to the world selected by the API version in its manifest
+        Conformance(err: wasmtime::Error) {
+            display("module doesn't conform to its API version: {err:#}")
+        }
+
+        /// Decoding the manifest failed
+        DecodeManifest(err: mrf_manifest::DecodeError) {
+            from()
+        }
+
+        /// Module doesn't contain a manifest
+        MissingManifest {
+            display("missing manifest in module")
+        }
+
+        /// Module ran out of fuel before finishing
+        OutOfFuel {
+            display("module exceeded its fuel budget")
+        }
+
+        /// API version isn't supported by this runtime
+        UnsupportedApiVersion {
+            display("unsupported API version")
+        }
+
+        /// Module requires a newer version of the host
+        UnsupportedHostVersion(required: semver::Version) {
+            display("module requires host version {required} or newer, running {HOST_VERSION}")
+        }
+
+        /// Module isn't signed by a trusted publisher
+        Signature(err: mrf_manifest::SignatureError) {
+            display("module signature rejected: {err}")
+            from()
+        }
+
+        /// Compilation, instantiation or execution of the module failed
+        Runtime(err: wasmtime::Error) {
+            display("{err}")
+            from()
+        }
+    }
+}
+
+/// Outcome of passing an activity through the MRF modules
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome<'a> {
+    /// The activity was accepted (potentially after modifications)
+    Accept(Cow<'a, str>),
+
+    /// The activity was rejected by one of the modules
+    Reject,
+}
+
+/// Resource limits applied to each invocation of a module
+#[derive(Builder, Clone, Copy, Debug)]
+pub struct Limits {
+    /// Fuel available to a single invocation
+    ///
+    /// Roughly corresponds to the amount of WASM instructions the module can execute before it is cut off
+    #[builder(default = 100_000_000)]
+    pub fuel: u64,
+
+    /// Maximum size of each linear memory in bytes
+    #[builder(default = 64 * 1024 * 1024)]
+    pub memory_size: usize,
+
+    /// Maximum amount of elements of each table
+    #[builder(default = 10_000)]
+    pub table_elements: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Construct an engine configured to execute MRF modules
+pub fn engine() -> Result<Engine, Error> {
+    let mut config = Config::new();
+    config.async_support(true).consume_fuel(true);
+
+    Engine::new(&config).map_err(Error::from)
+}
+
+/// Map running out of fuel onto its own error variant
+fn execution_error(error: wasmtime::Error) -> Error {
+    if error.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+        Error::OutOfFuel
+    } else {
+        Error::Runtime(error)
+    }
+}
+
+/// Pre-instantiate the component against the world selected by the API version
+///
+/// This fails if the component imports anything the world doesn't declare, or doesn't export what the world requires
+fn pre_instantiate(
+    engine: &Engine,
+    api_version: ApiVersion,
+    component: &Component,
+) -> Result<MrfV1Pre<Context>, Error> {
+    match api_version {
+        ApiVersion::V1 => {
+            let mut linker = Linker::new(engine);
+            bindings::MrfV1::add_to_linker::<_, HasSelf<Context>>(&mut linker, |ctx| ctx)?;
+
+            let instance_pre = linker
+                .instantiate_pre(component)
+                .map_err(Error::Conformance)?;
+
+            MrfV1Pre::new(instance_pre).map_err(Error::Conformance)
+        }
+        _ => Err(Error::UnsupportedApiVersion),
+    }
+}
+
+/// Check whether the component conforms to the world selected by the API version
+#[cfg_attr(not(coverage), instrument(skip_all))]
+pub fn check_conformance(
+    engine: &Engine,
+    api_version: ApiVersion,
+    module: &[u8],
+) -> Result<(), Error> {
+    let component = Component::new(engine, module)?;
+    pre_instantiate(engine, api_version, &component)?;
+
+    Ok(())
+}
+
+/// Compiled MRF module together with its manifest and configuration
+pub struct MrfModule {
+    config: String,
+    manifest: ManifestV2<'static>,
+    name: Arc<str>,
+    pre: MrfV1Pre<Context>,
+}
+
+impl MrfModule {
+    /// Load an MRF module
+    ///
+    /// The configuration is passed to the module on each invocation and is expected to be JSON-encoded.
+    /// Older manifest versions are upgraded to the latest one.
+    /// Modules requiring a newer host than [`HOST_VERSION`] are rejected.
+    #[cfg_attr(not(coverage), instrument(skip_all))]
+    pub fn load(engine: &Engine, module: &[u8], config: String) -> Result<Self, Error> {
+        let Some((manifest, _section_range)) = mrf_manifest::decode(module)? else {
+            return Err(Error::MissingManifest);
+        };
+
+        let manifest = manifest.upgrade().to_owned();
+
+        let host_version =
+            semver::Version::parse(HOST_VERSION).expect("[Bug] Invalid host version");
+        if let Some(required) = manifest
+            .min_host_version
+            .as_ref()
+            .filter(|required| **required > host_version)
+        {
+            return Err(Error::UnsupportedHostVersion(required.clone()));
+        }
+
+        debug!(name = %manifest.name, version = %manifest.version, "compiling module");
+        let component = Component::new(engine, module)?;
+        let pre = pre_instantiate(engine, manifest.api_version, &component)?;
+
+        Ok(Self {
+            config,
+            name: Arc::from(&*manifest.name),
+            manifest,
+            pre,
+        })
+    }
+
+    /// Load an MRF module after verifying it was signed by a key in the trust store
+    ///
+    /// See [`MrfModule::load`]
+    #[cfg_attr(not(coverage), instrument(skip_all))]
+    pub fn load_trusted(
+        engine: &Engine,
+        module: &[u8],
+        config: String,
+        trust_store: &TrustStore,
+    ) -> Result<Self, Error> {
+        let key = trust_store.verify(module)?;
+        debug!(key_id = %key.id, "verified module signature");
+
+        Self::load(engine, module, config)
+    }
+
+    /// Manifest of the module
+    #[must_use]
+    pub fn manifest(&self) -> &ManifestV2<'static> {
+        &self.manifest
+    }
+
+    /// Does the module want to receive activities of this type in this direction?
+    #[must_use]
+    pub fn handles(&self, direction: Direction, activity_type: &str) -> bool {
+        let Some(filter) = self.manifest.activity_types.get(activity_type) else {
+            return false;
+        };
+
+        match direction {
+            Direction::Incoming => {
+                self.manifest.has_capability(Capability::Incoming) && filter.incoming()
+            }
+            Direction::Outgoing => {
+                self.manifest.has_capability(Capability::Outgoing) && filter.outgoing()
+            }
+        }
+    }
+}
+
+/// Service executing a chain of MRF modules
+#[derive(Clone)]
+pub struct MrfService {
+    engine: Engine,
+    limits: Limits,
+    modules: Arc<[MrfModule]>,
+    storage: Arc<dyn KvStorage>,
+}
+
+#[bon]
+impl MrfService {
+    /// Construct a new service from a list of modules
+    ///
+    /// The modules have to be loaded with the same engine passed to this function.
+    /// They are executed in the order they are passed in.
+    ///
+    /// The storage defaults to a volatile [`MemoryStorage`], the limits to [`Limits::default`]
+    #[builder]
+    pub fn new(
+        engine: Engine,
+        modules: Vec<MrfModule>,
+        #[builder(default = Arc::new(MemoryStorage::default()))] storage: Arc<dyn KvStorage>,
+        #[builder(default)] limits: Limits,
+    ) -> Self {
+        Self {
+            engine,
+            limits,
+            modules: modules.into(),
+            storage,
+        }
+    }
+
+    /// Loaded modules
+    #[must_use]
+    pub fn modules(&self) -> &[MrfModule] {
+        &self.modules
+    }
+
+    /// Pass an activity through all the modules interested in its type
+    ///
+    /// If any of the modules rejects the activity, the remaining modules aren't executed anymore.
+    /// Modifications are passed on to the next module.
+    ///
+    /// Each module is executed within the [`Limits`] of the service. Growing memories or tables past them fails,
+    /// running out of fuel aborts the execution with [`Error::OutOfFuel`].
+    #[cfg_attr(not(coverage), instrument(skip(self, activity)))]
+    pub async fn handle<'a>(
+        &self,
+        direction: Direction,
+        activity_type: &str,
+        activity: &'a str,
+    ) -> Result<Outcome<'a>, Error> {
+        let mut activity = Cow::Borrowed(activity);
+
+        for module in self
+            .modules
+            .iter()
+            .filter(|module| module.handles(direction, activity_type))
+        {
+            let context = Context {
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(self.limits.memory_size)
+                    .table_elements(self.limits.table_elements)
+                    .build(),
+                logging: module.manifest.has_capability(Capability::Logging),
+                module_name: Arc::clone(&module.name),
+                storage: module
+                    .manifest
+                    .has_capability(Capability::Storage)
+                    .then(|| Arc::clone(&self.storage)),
+            };
+            let mut store = Store::new(&self.engine, context);
+            store.limiter(|context| &mut context.limits);
+            store.set_fuel(self.limits.fuel)?;
+            store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+
+            let mrf = module
+                .pre
+                .instantiate_async(&mut store)
+                .await
+                .map_err(execution_error)?;
+
+            let verdict = mrf
+                .call_transform(&mut store, &module.config, direction, &activity)
+                .await
+                .map_err(execution_error)?;
+
+            match verdict {
+                Verdict::Accept => {}
+                Verdict::Reject => {
+                    debug!(module = %module.name, "activity rejected");
+                    return Ok(Outcome::Reject);
+                }
+                Verdict::Modify(modified) => {
+                    debug!(module = %module.name, "activity modified");
+                    activity = Cow::Owned(modified);
+                }
+            }
+        }
+
+        Ok(Outcome::Accept(activity))
+    }
+}
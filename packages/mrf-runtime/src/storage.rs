@@ -0,0 +1,68 @@
+//!
+//! Key-value storage backing the `key-value` interface
+//!
+
+use async_trait::async_trait;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Boxed error with `Send` and `Sync` bounds
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Key-value storage backend
+///
+/// Every operation is scoped to the module identified by its name
+#[async_trait]
+pub trait KvStorage: Send + Sync {
+    /// Get the value stored under the key
+    async fn get(&self, module: &str, key: &str) -> Result<Option<Vec<u8>>, BoxError>;
+
+    /// Store a value under the key, replacing any previous value
+    async fn set(&self, module: &str, key: &str, value: Vec<u8>) -> Result<(), BoxError>;
+
+    /// Remove the value stored under the key
+    async fn remove(&self, module: &str, key: &str) -> Result<(), BoxError>;
+}
+
+/// Key-value pairs of a single module
+type Bucket = HashMap<String, Vec<u8>>;
+
+/// Volatile in-memory storage
+///
+/// Useful for testing or for modules which only need to cache data
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    inner: Arc<Mutex<HashMap<String, Bucket>>>,
+}
+
+#[async_trait]
+impl KvStorage for MemoryStorage {
+    async fn get(&self, module: &str, key: &str) -> Result<Option<Vec<u8>>, BoxError> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner
+            .get(module)
+            .and_then(|bucket| bucket.get(key))
+            .cloned())
+    }
+
+    async fn set(&self, module: &str, key: &str, value: Vec<u8>) -> Result<(), BoxError> {
+        let mut inner = self.inner.lock().unwrap();
+        inner
+            .entry(module.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+
+        Ok(())
+    }
+
+    async fn remove(&self, module: &str, key: &str) -> Result<(), BoxError> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(bucket) = inner.get_mut(module) {
+            bucket.remove(key);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use mrf_manifest::ApiVersion;
+use mrf_runtime::Error;
+
+mod util;
+
+const LOGGING_IMPORT: &str = r#"
+    (import "fep:mrf/logging@1.0.0" (instance $logging
+        (type $level' (enum "trace" "debug" "info" "warn" "error"))
+        (export "level" (type $level (eq $level')))
+        (export "log" (func (param "level" $level) (param "message" string)))
+    ))
+"#;
+
+const MISTYPED_LOGGING_IMPORT: &str = r#"
+    (import "fep:mrf/logging@1.0.0" (instance $logging
+        (export "log" (func (param "message" string)))
+    ))
+"#;
+
+const UNDECLARED_IMPORT: &str = r#"
+    (import "wasi:cli/environment@0.2.0" (instance $environment
+        (export "get-arguments" (func (result (list string))))
+    ))
+"#;
+
+fn check(module: &[u8]) -> Result<(), Error> {
+    let engine = mrf_runtime::engine().unwrap();
+    mrf_runtime::check_conformance(&engine, ApiVersion::V1, module)
+}
+
+#[test]
+fn conforming() {
+    check(&util::component(util::ACCEPT)).unwrap();
+    check(&util::component_with_imports(LOGGING_IMPORT, util::ACCEPT)).unwrap();
+}
+
+#[test]
+fn undeclared_import() {
+    let result = check(&util::component_with_imports(
+        UNDECLARED_IMPORT,
+        util::ACCEPT,
+    ));
+    assert!(matches!(result, Err(Error::Conformance(..))), "{result:?}");
+}
+
+#[test]
+fn mistyped_import() {
+    let result = check(&util::component_with_imports(
+        MISTYPED_LOGGING_IMPORT,
+        util::ACCEPT,
+    ));
+    assert!(matches!(result, Err(Error::Conformance(..))), "{result:?}");
+}
+
+#[test]
+fn missing_export() {
+    let component = wat::parse_str("(component)").unwrap();
+    let result = check(&component);
+    assert!(matches!(result, Err(Error::Conformance(..))), "{result:?}");
+}
@@ -0,0 +1,167 @@
+use self::util::{ACCEPT, MODIFY_WITH_CONFIG, REJECT, REJECT_OUTGOING};
+use mrf_manifest::{Capability, DirectionFilter};
+use mrf_runtime::{Direction, Error, MrfModule, MrfService, Outcome};
+use std::borrow::Cow;
+
+mod util;
+
+const ACTIVITY: &str = r#"{"type":"Create"}"#;
+
+fn service(modules: &[(Vec<u8>, &str)]) -> MrfService {
+    let engine = mrf_runtime::engine().unwrap();
+    let modules = modules
+        .iter()
+        .map(|(module, config)| MrfModule::load(&engine, module, (*config).to_string()).unwrap())
+        .collect();
+
+    MrfService::builder()
+        .engine(engine)
+        .modules(modules)
+        .build()
+}
+
+#[tokio::test]
+async fn accept() {
+    let service = service(&[(util::module("accept", &["*"], ACCEPT), "{}")]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+}
+
+#[tokio::test]
+async fn reject() {
+    let service = service(&[(util::module("reject", &["*"], REJECT), "{}")]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+}
+
+#[tokio::test]
+async fn direction_filter() {
+    let service = service(&[(
+        util::module_v2(
+            "reject-incoming-create",
+            &[Capability::Incoming, Capability::Outgoing],
+            &[("Create", DirectionFilter::Incoming)],
+            REJECT,
+        ),
+        "{}",
+    )]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+
+    let outcome = service
+        .handle(Direction::Outgoing, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+
+    let outcome = service
+        .handle(Direction::Incoming, "Like", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+}
+
+#[tokio::test]
+async fn direction_capability() {
+    let service = service(&[(
+        util::module_v2(
+            "reject-outgoing-only",
+            &[Capability::Outgoing],
+            &[("*", DirectionFilter::Both)],
+            REJECT,
+        ),
+        "{}",
+    )]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+
+    let outcome = service
+        .handle(Direction::Outgoing, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+}
+
+#[tokio::test]
+async fn direction() {
+    let service = service(&[(
+        util::module("reject-outgoing", &["*"], REJECT_OUTGOING),
+        "{}",
+    )]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+
+    let outcome = service
+        .handle(Direction::Outgoing, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+}
+
+#[tokio::test]
+async fn modify_chain() {
+    let service = service(&[
+        (
+            util::module("modify", &["Create"], MODIFY_WITH_CONFIG),
+            r#"{"type":"Note"}"#,
+        ),
+        (util::module("accept", &["*"], ACCEPT), "{}"),
+        (
+            util::module("modify-again", &["Create"], MODIFY_WITH_CONFIG),
+            r#"{"type":"Like"}"#,
+        ),
+    ]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(
+        outcome,
+        Outcome::Accept(Cow::Borrowed(r#"{"type":"Like"}"#))
+    );
+}
+
+#[tokio::test]
+async fn activity_type_filter() {
+    let service = service(&[(util::module("reject-likes", &["Like"], REJECT), "{}")]);
+
+    let outcome = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+
+    let outcome = service
+        .handle(Direction::Incoming, "Like", r#"{"type":"Like"}"#)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+}
+
+#[test]
+fn missing_manifest() {
+    let engine = mrf_runtime::engine().unwrap();
+    let result = MrfModule::load(&engine, &util::component(ACCEPT), String::new());
+    assert!(matches!(result, Err(Error::MissingManifest)));
+}
@@ -0,0 +1,97 @@
+use mrf_manifest::{
+    ActivityFilters, ApiVersion, Capability, DirectionFilter, Manifest, ManifestV2,
+};
+use mrf_runtime::{Direction, Error, Limits, MrfModule, MrfService, Outcome};
+use std::borrow::Cow;
+
+mod util;
+
+const ACTIVITY: &str = r#"{"type":"Create"}"#;
+
+/// Loops forever
+const INFINITE_LOOP: &str = "(loop $forever (br $forever))";
+
+/// Rejects the activity if growing the memory by 2000 pages (125 MiB) fails
+const GROW_MEMORY: &str = "
+    (i32.store8 (i32.const 16)
+        (i32.eq (memory.grow (i32.const 2000)) (i32.const -1)))
+";
+
+fn service(body: &str, limits: Limits) -> MrfService {
+    let engine = mrf_runtime::engine().unwrap();
+    let module =
+        MrfModule::load(&engine, &util::module("limited", &["*"], body), "{}".into()).unwrap();
+
+    MrfService::builder()
+        .engine(engine)
+        .modules(vec![module])
+        .limits(limits)
+        .build()
+}
+
+#[tokio::test]
+async fn infinite_loop_runs_out_of_fuel() {
+    let service = service(INFINITE_LOOP, Limits::builder().fuel(1_000_000).build());
+
+    let result = service
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await;
+    assert!(matches!(result, Err(Error::OutOfFuel)), "{result:?}");
+}
+
+#[tokio::test]
+async fn memory_growth_is_limited() {
+    let limited = service(GROW_MEMORY, Limits::default());
+    let outcome = limited
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Reject);
+
+    let generous = service(
+        GROW_MEMORY,
+        Limits::builder().memory_size(256 * 1024 * 1024).build(),
+    );
+    let outcome = generous
+        .handle(Direction::Incoming, "Create", ACTIVITY)
+        .await
+        .unwrap();
+    assert_eq!(outcome, Outcome::Accept(Cow::Borrowed(ACTIVITY)));
+}
+
+#[test]
+fn newer_host_version_required() {
+    let manifest = |min_host_version| {
+        Manifest::V2(ManifestV2 {
+            api_version: ApiVersion::V1,
+            name: Cow::Borrowed("future"),
+            version: semver::Version::new(1, 0, 0),
+            description: None,
+            authors: Vec::new(),
+            license: None,
+            homepage: None,
+            min_host_version: Some(min_host_version),
+            capabilities: [Capability::Incoming].into_iter().collect(),
+            activity_types: ActivityFilters(
+                [(Cow::Borrowed("*"), DirectionFilter::Both)]
+                    .into_iter()
+                    .collect(),
+            ),
+            config_schema: None,
+        })
+    };
+    let engine = mrf_runtime::engine().unwrap();
+
+    let module =
+        util::module_with_manifest(&manifest(semver::Version::new(999, 0, 0)), util::ACCEPT);
+    let result = MrfModule::load(&engine, &module, "{}".into());
+    assert!(
+        matches!(result, Err(Error::UnsupportedHostVersion(..))),
+        "{:?}",
+        result.err()
+    );
+
+    let host_version = semver::Version::parse(mrf_runtime::HOST_VERSION).unwrap();
+    let module = util::module_with_manifest(&manifest(host_version), util::ACCEPT);
+    MrfModule::load(&engine, &module, "{}".into()).unwrap();
+}
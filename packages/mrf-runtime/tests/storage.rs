@@ -0,0 +1,19 @@
+use mrf_runtime::{KvStorage, MemoryStorage};
+
+#[tokio::test]
+async fn memory_storage_is_scoped() {
+    let storage = MemoryStorage::default();
+
+    storage
+        .set("first", "key", b"value".to_vec())
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get("first", "key").await.unwrap().as_deref(),
+        Some(&b"value"[..])
+    );
+    assert_eq!(storage.get("second", "key").await.unwrap(), None);
+
+    storage.remove("first", "key").await.unwrap();
+    assert_eq!(storage.get("first", "key").await.unwrap(), None);
+}
@@ -0,0 +1,43 @@
+use self::util::ACCEPT;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use http_signatures::ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use mrf_manifest::{SignatureError, TrustStore, TrustedKey};
+use mrf_runtime::{Error, MrfModule};
+
+mod util;
+
+/// DER prefix of an Ed25519 public key in its SPKI form
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[test]
+fn load_trusted() {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let trust_store = TrustStore {
+        keys: vec![TrustedKey {
+            id: "publisher".into(),
+            public_key: BASE64_STANDARD
+                .encode([ED25519_SPKI_PREFIX, key_pair.public_key().as_ref()].concat()),
+        }],
+    };
+
+    let engine = mrf_runtime::engine().unwrap();
+    let module = util::module("accept", &["*"], ACCEPT);
+
+    let error = MrfModule::load_trusted(&engine, &module, "{}".into(), &trust_store)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        Error::Signature(SignatureError::MissingSignature)
+    ));
+
+    let signed = mrf_manifest::sign(&module, "publisher", &key_pair).unwrap();
+    let module = MrfModule::load_trusted(&engine, &signed, "{}".into(), &trust_store).unwrap();
+    assert_eq!(module.manifest().name, "accept");
+}
@@ -0,0 +1,128 @@
+#![allow(dead_code)]
+
+use mrf_manifest::{
+    ActivityFilters, ActivitySet, ApiVersion, Capability, DirectionFilter, Manifest, ManifestV1,
+    ManifestV2,
+};
+use std::borrow::Cow;
+
+pub const ACCEPT: &str = "(i32.store8 (i32.const 16) (i32.const 0))";
+pub const REJECT: &str = "(i32.store8 (i32.const 16) (i32.const 1))";
+
+/// Replace the activity with the configuration
+pub const MODIFY_WITH_CONFIG: &str = "
+    (i32.store8 (i32.const 16) (i32.const 2))
+    (i32.store (i32.const 20) (local.get $config_ptr))
+    (i32.store (i32.const 24) (local.get $config_len))
+";
+
+/// Accept incoming activities, reject outgoing ones
+pub const REJECT_OUTGOING: &str = "(i32.store8 (i32.const 16) (local.get $direction))";
+
+/// Build an MRF component whose `transform` function executes the body and returns the verdict written to address 16
+#[must_use]
+pub fn component(body: &str) -> Vec<u8> {
+    component_with_imports("", body)
+}
+
+/// Build an MRF component with the given component-level imports
+///
+/// The imports aren't made available to the core module
+#[must_use]
+pub fn component_with_imports(imports: &str, body: &str) -> Vec<u8> {
+    wat::parse_str(format!(
+        r#"
+(component
+    {imports}
+
+    (core module $m
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+            (local.get $ptr)
+        )
+
+        (func (export "transform")
+            (param $config_ptr i32) (param $config_len i32)
+            (param $direction i32)
+            (param $activity_ptr i32) (param $activity_len i32)
+            (result i32)
+
+            {body}
+            (i32.const 16)
+        )
+    )
+    (core instance $i (instantiate $m))
+
+    (type $direction' (enum "incoming" "outgoing"))
+    (export $direction "direction" (type $direction'))
+    (type $verdict' (variant (case "accept") (case "reject") (case "modify" string)))
+    (export $verdict "verdict" (type $verdict'))
+
+    (func $transform
+        (param "configuration" string) (param "direction" $direction) (param "activity" string)
+        (result $verdict)
+        (canon lift (core func $i "transform") (memory $i "memory") (realloc (func $i "realloc")))
+    )
+    (export "transform" (func $transform))
+)
+"#
+    ))
+    .unwrap()
+}
+
+/// Build an MRF component with an embedded manifest
+#[must_use]
+pub fn module(name: &str, activity_types: &[&str], body: &str) -> Vec<u8> {
+    let manifest = Manifest::V1(ManifestV1 {
+        api_version: ApiVersion::V1,
+        name: Cow::Borrowed(name),
+        version: semver::Version::new(1, 0, 0),
+        activity_types: ActivitySet(activity_types.iter().copied().map(Cow::Borrowed).collect()),
+        config_schema: None,
+    });
+
+    module_with_manifest(&manifest, body)
+}
+
+/// Build an MRF component with an embedded v2 manifest
+#[must_use]
+pub fn module_v2(
+    name: &str,
+    capabilities: &[Capability],
+    activity_types: &[(&str, DirectionFilter)],
+    body: &str,
+) -> Vec<u8> {
+    let manifest = Manifest::V2(ManifestV2 {
+        api_version: ApiVersion::V1,
+        name: Cow::Borrowed(name),
+        version: semver::Version::new(1, 0, 0),
+        description: None,
+        authors: Vec::new(),
+        license: None,
+        homepage: None,
+        min_host_version: None,
+        capabilities: capabilities.iter().copied().collect(),
+        activity_types: ActivityFilters(
+            activity_types
+                .iter()
+                .map(|(activity_type, filter)| (Cow::Borrowed(*activity_type), *filter))
+                .collect(),
+        ),
+        config_schema: None,
+    });
+
+    module_with_manifest(&manifest, body)
+}
+
+/// Build an MRF component embedding the manifest
+#[must_use]
+pub fn module_with_manifest(manifest: &Manifest<'_>, body: &str) -> Vec<u8> {
+    let mut module = component(body);
+    module.extend(mrf_manifest::encode(manifest).unwrap());
+    module
+}
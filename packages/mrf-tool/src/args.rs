@@ -1,4 +1,5 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
 use std::path::PathBuf;
 
 #[derive(Args)]
@@ -12,6 +13,78 @@ pub struct AddManifest {
     /// Path to where the modified WASM module should be written
     #[arg(long, short)]
     pub output: PathBuf,
+
+    /// Remove any existing manifest sections before adding the manifest
+    #[arg(long)]
+    pub replace: bool,
+}
+
+#[derive(Args)]
+pub struct BuildModule {
+    /// Path to the project directory
+    #[arg(long, short, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Build in release mode
+    #[arg(long)]
+    pub release: bool,
+
+    /// Path to where the WASM module with the embedded manifest should be written
+    ///
+    /// Defaults to `<crate name>.mrf.wasm` next to the compiled module
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct SignModule {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Path to the Ed25519 private key in its PKCS#8 form (PEM or DER)
+    #[arg(long, short)]
+    pub key: PathBuf,
+
+    /// ID of the key, used to look up the public key in the trust store
+    #[arg(long)]
+    pub key_id: String,
+
+    /// Path to where the signed WASM module should be written
+    #[arg(long, short)]
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct VerifyModule {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Path to the trust store (TOML)
+    #[arg(long, short)]
+    pub trust_store: PathBuf,
+}
+
+#[derive(Args)]
+pub struct ValidateConfig {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Path to the configuration
+    ///
+    /// Parsed as JSON if the file ends with `.json`, as TOML otherwise
+    pub config_path: PathBuf,
+}
+
+#[derive(Args)]
+pub struct NewModule {
+    /// Name of the module
+    pub name: String,
+
+    /// Path to the project directory
+    ///
+    /// Defaults to the name of the module
+    #[arg(long, short)]
+    pub path: Option<PathBuf>,
 }
 
 #[derive(Args)]
@@ -30,18 +103,92 @@ pub struct RemoveManifest {
     pub output: PathBuf,
 }
 
+/// Direction the activity is passed in
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityDirection {
+    /// Activity received from another server
+    #[default]
+    Incoming,
+
+    /// Activity sent to another server
+    Outgoing,
+}
+
+impl From<ActivityDirection> for mrf_runtime::Direction {
+    fn from(value: ActivityDirection) -> Self {
+        match value {
+            ActivityDirection::Incoming => Self::Incoming,
+            ActivityDirection::Outgoing => Self::Outgoing,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct InspectModule {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Print the information as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct RunModule {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Path to the configuration
+    ///
+    /// Parsed as JSON if the file ends with `.json`, as TOML otherwise.
+    /// Defaults to an empty object
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+
+    /// Path to the activity (JSON)
+    #[arg(long, short)]
+    pub activity: PathBuf,
+
+    /// Direction the activity is passed in
+    #[arg(long, short, value_enum, default_value_t)]
+    pub direction: ActivityDirection,
+}
+
+#[derive(Args)]
+pub struct TestModule {
+    /// Path to the WASM module
+    pub module_path: PathBuf,
+
+    /// Path to the directory containing the fixtures
+    pub fixtures_path: PathBuf,
+
+    /// Path to the configuration
+    ///
+    /// Parsed as JSON if the file ends with `.json`, as TOML otherwise.
+    /// Defaults to an empty object
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+}
+
 #[derive(Args)]
 pub struct ValidateModule {
     /// Path to the WASM module
     pub module_path: PathBuf,
 }
 
+#[derive(Subcommand)]
+pub enum ConfigSubcommand {
+    /// Validate a configuration against the schema embedded into the module's manifest
+    Validate(ValidateConfig),
+}
+
 #[derive(Subcommand)]
 pub enum ManifestSubcommand {
     /// Add a manifest to a WASM component
     ///
-    /// Note: We don't validate whether the WASM component already contains a manifest section.
-    /// We simply append a new section.
+    /// Fails if the WASM component already contains a manifest section, unless `--replace` is passed.
+    /// In that case all existing manifest sections are removed before the new one is appended.
     Add(AddManifest),
 
     /// Read the manifest from a WASM component
@@ -53,12 +200,49 @@ pub enum ManifestSubcommand {
 
 #[derive(Subcommand)]
 pub enum ModuleSubcommand {
+    /// Print information about a WASM module
+    ///
+    /// Includes whether it's a core module or a component, its imports and exports, its WIT world, its custom sections and memories.
+    /// If the module contains a manifest, it is additionally checked against the world selected by the manifest's API version.
+    Inspect(InspectModule),
+
+    /// Run a WASM module over an activity in a local sandbox
+    ///
+    /// Prints the verdict and the modified activity, if the module modified it.
+    /// The storage of the module is volatile.
+    Run(RunModule),
+
+    /// Run a WASM module over a directory of fixtures and compare the verdicts
+    ///
+    /// Each fixture is a JSON file with the fields `activity`, `verdict` (`accept`, `reject` or `modify`),
+    /// and optionally `direction` (`incoming` or `outgoing`, defaults to `incoming`)
+    /// and `modified` (the expected activity after the modification).
+    ///
+    /// The fixtures are run in alphabetical order and share the storage of the module.
+    Test(TestModule),
+
     /// Validate a WASM module
+    ///
+    /// If the module contains a manifest, it is additionally checked against the world selected by the manifest's API version.
+    /// This fails if the module imports anything the world doesn't declare.
     Validate(ValidateModule),
 }
 
 #[derive(Subcommand)]
 pub enum ToolSubcommand {
+    /// Build an MRF module project created via `new`
+    ///
+    /// Compiles the project into a WASM component and embeds the manifest.
+    /// The version is taken from `Cargo.toml` and the configuration schema is generated from the `Config` struct.
+    Build(BuildModule),
+
+    /// Manage configurations of modules
+    #[clap(subcommand)]
+    Config(ConfigSubcommand),
+
+    /// Create a new MRF module project
+    New(NewModule),
+
     /// Manage manifests embedded into modules
     #[clap(subcommand)]
     Manifest(ManifestSubcommand),
@@ -66,6 +250,18 @@ pub enum ToolSubcommand {
     /// Manage WASM MRF modules
     #[clap(subcommand)]
     Module(ModuleSubcommand),
+
+    /// Sign a WASM module and its manifest
+    ///
+    /// The signature covers the module (excluding the signature itself) and the canonical form of the manifest.
+    /// An existing signature is replaced.
+    Sign(SignModule),
+
+    /// Verify that a WASM module is signed by a key in the trust store
+    ///
+    /// The trust store is a TOML file containing a `keys` array.
+    /// Each entry consists of an `id` and a `public_key`, which is the body of the PEM-encoded Ed25519 public key.
+    Verify(VerifyModule),
 }
 
 #[derive(Parser)]
@@ -17,7 +17,10 @@ pub trait Filesystem {
         Self: 'a;
 
     fn copy(&mut self, src: &Path, dst: &Path) -> io::Result<()>;
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()>;
+    fn exists(&mut self, path: &Path) -> bool;
     fn read(&mut self, path: &Path) -> io::Result<Vec<u8>>;
+    fn read_dir(&mut self, path: &Path) -> io::Result<Vec<PathBuf>>;
 
     fn create_or_truncate(&mut self, path: &Path) -> io::Result<Self::File<'_>>;
     fn open_append(&mut self, path: &Path) -> io::Result<Self::File<'_>>;
@@ -53,14 +56,38 @@ impl Filesystem for DummyFs {
         Ok(())
     }
 
+    #[inline]
+    fn create_dir_all(&mut self, _path: &Path) -> io::Result<()> {
+        // Directories are implicit
+        Ok(())
+    }
+
+    #[inline]
+    fn exists(&mut self, path: &Path) -> bool {
+        self.inner.keys().any(|key| key.starts_with(path))
+    }
+
     #[inline]
     fn read(&mut self, path: &Path) -> io::Result<Vec<u8>> {
         self.inner.get(path).cloned().ok_or_else(file_not_found)
     }
 
+    #[inline]
+    fn read_dir(&mut self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        Ok(self
+            .inner
+            .keys()
+            .filter(|key| key.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
     #[inline]
     fn create_or_truncate(&mut self, path: &Path) -> io::Result<Self::File<'_>> {
-        Ok(self.inner.entry(path.to_path_buf()).or_default())
+        let file = self.inner.entry(path.to_path_buf()).or_default();
+        file.clear();
+
+        Ok(file)
     }
 
     #[inline]
@@ -83,11 +110,28 @@ impl Filesystem for NativeFs {
         Ok(())
     }
 
+    #[inline]
+    fn create_dir_all(&mut self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    #[inline]
+    fn exists(&mut self, path: &Path) -> bool {
+        path.exists()
+    }
+
     #[inline]
     fn read(&mut self, path: &Path) -> io::Result<Vec<u8>> {
         fs::read(path)
     }
 
+    #[inline]
+    fn read_dir(&mut self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
     #[inline]
     fn create_or_truncate(&mut self, path: &Path) -> io::Result<Self::File<'_>> {
         File::options()
@@ -0,0 +1,322 @@
+use color_eyre::{Result, eyre::eyre};
+use serde::Serialize;
+use std::io::Write;
+use wasmparser::{ComponentExternalKind, Encoding, ExternalKind, MemoryType, Payload, TypeRef};
+use wit_component::{DecodedWasm, WitPrinter};
+
+/// Whether the binary is a core module or a component
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BinaryKind {
+    Component,
+    Module,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Item {
+    pub name: String,
+    pub kind: &'static str,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CustomSection {
+    pub name: String,
+    pub size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Memory {
+    pub initial_pages: u64,
+    pub maximum_pages: Option<u64>,
+    pub memory64: bool,
+    pub shared: bool,
+}
+
+impl From<MemoryType> for Memory {
+    fn from(value: MemoryType) -> Self {
+        Self {
+            initial_pages: value.initial,
+            maximum_pages: value.maximum,
+            memory64: value.memory64,
+            shared: value.shared,
+        }
+    }
+}
+
+/// Result of checking the module against the world selected by the API version in its manifest
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conformance {
+    pub api_version: mrf_manifest::ApiVersion,
+    pub conforms: bool,
+    pub error: Option<String>,
+}
+
+/// Information about a WASM binary
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inspection {
+    pub kind: BinaryKind,
+    pub size: usize,
+    pub imports: Vec<Item>,
+    pub exports: Vec<Item>,
+
+    /// WIT world of the component, rendered as WIT
+    pub world: Option<String>,
+
+    /// Custom sections of the top-level binary
+    pub custom_sections: Vec<CustomSection>,
+
+    /// Memories defined or imported by all core modules in the binary
+    pub memories: Vec<Memory>,
+
+    /// `None` if the binary doesn't carry a manifest
+    pub conformance: Option<Conformance>,
+}
+
+fn core_kind(kind: ExternalKind) -> &'static str {
+    match kind {
+        ExternalKind::Func => "func",
+        ExternalKind::Table => "table",
+        ExternalKind::Memory => "memory",
+        ExternalKind::Global => "global",
+        ExternalKind::Tag => "tag",
+    }
+}
+
+fn component_kind(kind: ComponentExternalKind) -> &'static str {
+    match kind {
+        ComponentExternalKind::Module => "module",
+        ComponentExternalKind::Func => "func",
+        ComponentExternalKind::Value => "value",
+        ComponentExternalKind::Type => "type",
+        ComponentExternalKind::Instance => "instance",
+        ComponentExternalKind::Component => "component",
+    }
+}
+
+/// Render the world of a component as WIT
+///
+/// Returns `None` if the type information couldn't be decoded
+fn render_world(binary: &[u8]) -> Option<String> {
+    let DecodedWasm::Component(resolve, world) = wit_component::decode(binary).ok()? else {
+        return None;
+    };
+
+    let package = resolve.worlds[world].package?;
+    let mut printer = WitPrinter::default();
+    printer.print(&resolve, package, &[]).ok()?;
+
+    Some(printer.output.to_string())
+}
+
+fn check_conformance(binary: &[u8]) -> Result<Option<Conformance>> {
+    let Some((manifest, _section_range)) = mrf_manifest::decode(binary)? else {
+        return Ok(None);
+    };
+
+    let api_version = manifest.api_version();
+    let engine = mrf_runtime::engine()?;
+    let error = mrf_runtime::check_conformance(&engine, api_version, binary)
+        .err()
+        .map(|error| error.to_string());
+
+    Ok(Some(Conformance {
+        api_version,
+        conforms: error.is_none(),
+        error,
+    }))
+}
+
+/// Collect information about a WASM binary
+pub fn inspect(binary: &[u8]) -> Result<Inspection> {
+    wasmparser::validate(binary)?;
+
+    let mut kind = None;
+    let mut depth = 0_usize;
+    let mut imports = Vec::new();
+    let mut exports = Vec::new();
+    let mut custom_sections = Vec::new();
+    let mut memories = Vec::new();
+
+    for payload in wasmparser::Parser::new(0).parse_all(binary) {
+        match payload? {
+            Payload::Version { encoding, .. } => {
+                depth += 1;
+
+                if depth == 1 {
+                    kind = Some(match encoding {
+                        Encoding::Component => BinaryKind::Component,
+                        Encoding::Module => BinaryKind::Module,
+                    });
+                }
+            }
+            Payload::End(..) => depth -= 1,
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    if let TypeRef::Memory(memory) = import.ty {
+                        memories.push(memory.into());
+                    }
+
+                    if depth == 1 {
+                        let kind = match import.ty {
+                            TypeRef::Func(..) => "func",
+                            TypeRef::Table(..) => "table",
+                            TypeRef::Memory(..) => "memory",
+                            TypeRef::Global(..) => "global",
+                            TypeRef::Tag(..) => "tag",
+                        };
+
+                        imports.push(Item {
+                            name: format!("{}::{}", import.module, import.name),
+                            kind,
+                        });
+                    }
+                }
+            }
+            Payload::ExportSection(reader) if depth == 1 => {
+                for export in reader {
+                    let export = export?;
+                    exports.push(Item {
+                        name: export.name.into(),
+                        kind: core_kind(export.kind),
+                    });
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    memories.push(memory?.into());
+                }
+            }
+            Payload::ComponentImportSection(reader) if depth == 1 => {
+                for import in reader {
+                    let import = import?;
+                    imports.push(Item {
+                        name: import.name.0.into(),
+                        kind: component_kind(import.ty.kind()),
+                    });
+                }
+            }
+            Payload::ComponentExportSection(reader) if depth == 1 => {
+                for export in reader {
+                    let export = export?;
+                    exports.push(Item {
+                        name: export.name.0.into(),
+                        kind: component_kind(export.kind),
+                    });
+                }
+            }
+            Payload::CustomSection(reader) if depth == 1 => {
+                custom_sections.push(CustomSection {
+                    name: reader.name().into(),
+                    size: reader.data().len(),
+                });
+            }
+            _ => {
+                // Not relevant for the inspection. Skip.
+            }
+        }
+    }
+
+    let kind = kind.ok_or_else(|| eyre!("missing version header"))?;
+    let world = (kind == BinaryKind::Component)
+        .then(|| render_world(binary))
+        .flatten();
+
+    Ok(Inspection {
+        kind,
+        size: binary.len(),
+        imports,
+        exports,
+        world,
+        custom_sections,
+        memories,
+        conformance: check_conformance(binary)?,
+    })
+}
+
+/// Write a human-readable version of the inspection
+pub fn write_inspection<W>(sink: &mut W, inspection: &Inspection) -> Result<()>
+where
+    W: Write,
+{
+    let kind = match inspection.kind {
+        BinaryKind::Component => "component",
+        BinaryKind::Module => "core module",
+    };
+    writeln!(sink, "Kind: {kind}")?;
+    writeln!(sink, "Size: {} bytes", inspection.size)?;
+
+    for (title, items) in [
+        ("Imports", &inspection.imports),
+        ("Exports", &inspection.exports),
+    ] {
+        writeln!(sink, "\n{title}:")?;
+        if items.is_empty() {
+            writeln!(sink, "  (none)")?;
+        }
+        for item in items {
+            writeln!(sink, "  {} ({})", item.name, item.kind)?;
+        }
+    }
+
+    if let Some(ref world) = inspection.world {
+        writeln!(sink, "\nWorld:")?;
+        for line in world.lines() {
+            if line.is_empty() {
+                writeln!(sink)?;
+            } else {
+                writeln!(sink, "  {line}")?;
+            }
+        }
+    }
+
+    writeln!(sink, "\nCustom sections:")?;
+    if inspection.custom_sections.is_empty() {
+        writeln!(sink, "  (none)")?;
+    }
+    for section in &inspection.custom_sections {
+        writeln!(sink, "  {} ({} bytes)", section.name, section.size)?;
+    }
+
+    writeln!(sink, "\nMemories:")?;
+    if inspection.memories.is_empty() {
+        writeln!(sink, "  (none)")?;
+    }
+    for (idx, memory) in inspection.memories.iter().enumerate() {
+        let maximum = memory.maximum_pages.map_or_else(
+            || "no maximum".into(),
+            |maximum| format!("{maximum} pages maximum"),
+        );
+        let address_width = if memory.memory64 { 64 } else { 32 };
+        let shared = if memory.shared { ", shared" } else { "" };
+
+        writeln!(
+            sink,
+            "  #{idx}: {} pages initial, {maximum} ({address_width}-bit{shared})",
+            memory.initial_pages,
+        )?;
+    }
+
+    write!(sink, "\nConformance: ")?;
+    match inspection.conformance {
+        Some(ref conformance) => {
+            let api_version = format!("{:?}", conformance.api_version).to_lowercase();
+
+            if conformance.conforms {
+                writeln!(sink, "conforms to API {api_version}")?;
+            } else {
+                writeln!(
+                    sink,
+                    "doesn't conform to API {api_version}: {}",
+                    conformance.error.as_deref().unwrap_or_default(),
+                )?;
+            }
+        }
+        None => writeln!(sink, "unknown (no manifest)")?,
+    }
+
+    Ok(())
+}
@@ -1,12 +1,29 @@
-use self::args::{ManifestSubcommand, ModuleSubcommand, ToolArgs, ToolSubcommand};
+use self::args::{
+    ConfigSubcommand, ManifestSubcommand, ModuleSubcommand, ToolArgs, ToolSubcommand,
+};
 use clap::Parser;
-use color_eyre::{Result, eyre::bail};
-use std::{ffi::OsString, io::Write, path::Path};
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use http_signatures::ring::signature::Ed25519KeyPair;
+use mrf_manifest::{SectionRange, TrustStore};
+use pkcs8::SecretDocument;
+use std::{ffi::OsString, io::Write, path::Path, str};
 
-pub use self::fs::{DummyFs, Filesystem, NativeFs};
+pub use self::{
+    args::ActivityDirection,
+    fs::{DummyFs, Filesystem, NativeFs},
+    inspect::{Inspection, inspect, write_inspection},
+    run::{Sandbox, Verdict, run_module, test_module},
+    scaffold::{assemble_manifest, new_project},
+};
 
 mod args;
 mod fs;
+mod inspect;
+mod run;
+mod scaffold;
 
 pub fn read_manifest<W>(sink: &mut W, module: &[u8]) -> Result<()>
 where
@@ -22,23 +39,104 @@ where
     Ok(())
 }
 
+/// Remove the byte ranges from the module
+///
+/// The ranges have to be sorted and must not overlap
+fn remove_sections(module: &[u8], section_ranges: &[SectionRange]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(module.len());
+    let mut start = 0;
+    for section_range in section_ranges {
+        stripped.extend_from_slice(&module[start..section_range.start]);
+        start = section_range.end;
+    }
+    stripped.extend_from_slice(&module[start..]);
+
+    stripped
+}
+
 pub fn remove_manifest<F>(fs: &mut F, module_path: &Path, output_path: &Path) -> Result<()>
 where
     F: Filesystem,
 {
     let module = fs.read(module_path)?;
-    let Some((_manifest, section_range)) = mrf_manifest::decode(&module)? else {
+    let section_ranges = mrf_manifest::manifest_sections(&module)?;
+    if section_ranges.is_empty() {
         bail!("missing manifest in module");
-    };
+    }
 
     let mut module_file = fs.create_or_truncate(output_path)?;
-    module_file.write_all(&module[..section_range.start])?;
-    module_file.write_all(&module[section_range.end..])?;
+    module_file.write_all(&remove_sections(&module, &section_ranges))?;
+
+    Ok(())
+}
+
+pub fn sign_module(module: &[u8], key: &[u8], key_id: &str) -> Result<Vec<u8>> {
+    let key = if key.starts_with(b"-----BEGIN") {
+        let (_label, document) = SecretDocument::from_pem(str::from_utf8(key)?)?;
+        Ed25519KeyPair::from_pkcs8_maybe_unchecked(document.as_bytes())
+    } else {
+        Ed25519KeyPair::from_pkcs8_maybe_unchecked(key)
+    }
+    .map_err(|error| eyre!("invalid Ed25519 key: {error}"))?;
+
+    Ok(mrf_manifest::sign(module, key_id, &key)?)
+}
+
+pub fn verify_module<W>(sink: &mut W, module: &[u8], trust_store: &[u8]) -> Result<()>
+where
+    W: Write,
+{
+    let trust_store: TrustStore = toml::from_slice(trust_store)?;
+    let key = trust_store.verify(module)?;
+    writeln!(sink, "module signed by trusted key \"{}\"", key.id)?;
 
     Ok(())
 }
 
-pub fn write_manifest<F>(fs: &mut F, manifest: &[u8], module_path: &Path) -> Result<()>
+/// Parse a configuration as JSON if the file ends with `.json`, as TOML otherwise
+pub(crate) fn parse_config(config: &[u8], config_path: &Path) -> Result<serde_json::Value> {
+    let config = if config_path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_slice(config)?
+    } else {
+        toml::from_slice(config)?
+    };
+
+    Ok(config)
+}
+
+fn read_config<F>(fs: &mut F, config_path: Option<&Path>) -> Result<Option<serde_json::Value>>
+where
+    F: Filesystem,
+{
+    config_path
+        .map(|config_path| parse_config(&fs.read(config_path)?, config_path))
+        .transpose()
+}
+
+pub fn validate_config(module: &[u8], config: &[u8], config_path: &Path) -> Result<()> {
+    let Some((manifest, _section_range)) = mrf_manifest::decode(module)? else {
+        bail!("missing manifest in module");
+    };
+
+    if manifest.config_schema().is_none() {
+        bail!("manifest doesn't contain a configuration schema");
+    }
+
+    let config = parse_config(config, config_path)?;
+    manifest.validate_config(&config)?;
+
+    Ok(())
+}
+
+pub fn write_manifest<F>(
+    fs: &mut F,
+    manifest: &[u8],
+    module_path: &Path,
+    replace: bool,
+) -> Result<()>
 where
     F: Filesystem,
 {
@@ -46,8 +144,32 @@ where
     let parsed_manifest = serde_json::from_slice(manifest)?;
     let custom_section = mrf_manifest::encode(&parsed_manifest)?;
 
-    let mut file = fs.open_append(module_path)?;
-    file.write_all(&custom_section)?;
+    let module = fs.read(module_path)?;
+    let section_ranges = mrf_manifest::manifest_sections(&module)?;
+
+    if section_ranges.is_empty() {
+        let mut file = fs.open_append(module_path)?;
+        file.write_all(&custom_section)?;
+    } else if replace {
+        let mut file = fs.create_or_truncate(module_path)?;
+        file.write_all(&remove_sections(&module, &section_ranges))?;
+        file.write_all(&custom_section)?;
+    } else {
+        bail!("module already contains a manifest (pass `--replace` to replace it)");
+    }
+
+    Ok(())
+}
+
+pub fn validate_module(module: &[u8]) -> Result<()> {
+    wasmparser::validate(module)?;
+
+    let Some((manifest, _section_range)) = mrf_manifest::decode(module)? else {
+        return Ok(());
+    };
+
+    let engine = mrf_runtime::engine()?;
+    mrf_runtime::check_conformance(&engine, manifest.api_version(), module)?;
 
     Ok(())
 }
@@ -61,6 +183,18 @@ where
 {
     let args = ToolArgs::try_parse_from(input)?;
     match args.command {
+        ToolSubcommand::Build(args) => {
+            self::scaffold::build_project(fs, sink, &args)?;
+        }
+        ToolSubcommand::Config(ConfigSubcommand::Validate(args)) => {
+            let module = fs.read(&args.module_path)?;
+            let config = fs.read(&args.config_path)?;
+            validate_config(&module, &config, &args.config_path)?;
+        }
+        ToolSubcommand::New(args) => {
+            let project_path = args.path.unwrap_or_else(|| args.name.clone().into());
+            new_project(fs, &args.name, &project_path)?;
+        }
         ToolSubcommand::Manifest(ManifestSubcommand::Add(args)) => {
             let manifest = fs.read(&args.manifest_path)?;
 
@@ -69,7 +203,7 @@ where
                 fs.copy(&args.module_path, &args.output)?;
             }
 
-            write_manifest(fs, &manifest, &args.output)?;
+            write_manifest(fs, &manifest, &args.output, args.replace)?;
         }
         ToolSubcommand::Manifest(ManifestSubcommand::Read(args)) => {
             let data = fs.read(&args.module_path)?;
@@ -78,9 +212,44 @@ where
         ToolSubcommand::Manifest(ManifestSubcommand::Remove(args)) => {
             remove_manifest(fs, &args.module_path, &args.output)?;
         }
+        ToolSubcommand::Module(ModuleSubcommand::Inspect(args)) => {
+            let data = fs.read(&args.module_path)?;
+            let inspection = inspect(&data)?;
+
+            if args.json {
+                serde_json::to_writer_pretty(&mut *sink, &inspection)?;
+                writeln!(sink)?;
+            } else {
+                write_inspection(sink, &inspection)?;
+            }
+        }
+        ToolSubcommand::Module(ModuleSubcommand::Run(args)) => {
+            let module = fs.read(&args.module_path)?;
+            let config = read_config(fs, args.config.as_deref())?;
+            let activity = fs.read(&args.activity)?;
+            run_module(sink, &module, config, args.direction, &activity)?;
+        }
+        ToolSubcommand::Module(ModuleSubcommand::Test(args)) => {
+            let module = fs.read(&args.module_path)?;
+            let config = read_config(fs, args.config.as_deref())?;
+            test_module(fs, sink, &module, config, &args.fixtures_path)?;
+        }
         ToolSubcommand::Module(ModuleSubcommand::Validate(args)) => {
             let data = fs.read(&args.module_path)?;
-            wasmparser::validate(&data)?;
+            validate_module(&data)?;
+        }
+        ToolSubcommand::Sign(args) => {
+            let module = fs.read(&args.module_path)?;
+            let key = fs.read(&args.key)?;
+            let signed = sign_module(&module, &key, &args.key_id)?;
+
+            let mut file = fs.create_or_truncate(&args.output)?;
+            file.write_all(&signed)?;
+        }
+        ToolSubcommand::Verify(args) => {
+            let module = fs.read(&args.module_path)?;
+            let trust_store = fs.read(&args.trust_store)?;
+            verify_module(sink, &module, &trust_store)?;
         }
     }
 
@@ -0,0 +1,229 @@
+use crate::{Filesystem, args::ActivityDirection};
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use mrf_runtime::{MrfModule, MrfService, Outcome};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{borrow::Cow, fmt, io::Write, path::Path};
+use tokio::runtime::Runtime;
+
+/// Verdict of the module over an activity
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    Accept,
+    Reject,
+    Modify(Value),
+
+    /// The filters in the manifest exclude the activity, so the module wasn't executed
+    Skipped,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => f.write_str("accept"),
+            Self::Reject => f.write_str("reject"),
+            Self::Modify(..) => f.write_str("modify"),
+            Self::Skipped => f.write_str("skipped (filtered)"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum ExpectedVerdict {
+    Accept,
+    Reject,
+    Modify,
+    Skipped,
+}
+
+/// Activity together with the verdict the module is expected to return
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fixture {
+    #[serde(default)]
+    direction: ActivityDirection,
+    activity: Value,
+    verdict: ExpectedVerdict,
+
+    /// Expected activity after the modification, only checked if the expected verdict is `modify`
+    #[serde(default)]
+    modified: Option<Value>,
+}
+
+impl Fixture {
+    /// Check the verdict against the expectations
+    ///
+    /// Returns a description of the mismatch if they don't match
+    fn check(&self, verdict: &Verdict) -> Option<String> {
+        match (self.verdict, verdict) {
+            (ExpectedVerdict::Accept, Verdict::Accept)
+            | (ExpectedVerdict::Reject, Verdict::Reject)
+            | (ExpectedVerdict::Skipped, Verdict::Skipped) => None,
+            (ExpectedVerdict::Modify, Verdict::Modify(modified)) => match self.modified {
+                Some(ref expected) if expected != modified => Some(format!(
+                    "expected the activity to be modified into {expected}, got {modified}"
+                )),
+                _ => None,
+            },
+            (expected, verdict) => Some(format!(
+                "expected verdict {}, got {verdict}",
+                format!("{expected:?}").to_lowercase()
+            )),
+        }
+    }
+}
+
+/// Local sandbox executing a single module
+///
+/// The module is executed within the same default limits the runtime applies
+pub struct Sandbox {
+    runtime: Runtime,
+    service: MrfService,
+}
+
+impl Sandbox {
+    /// Load the module with the configuration
+    ///
+    /// The configuration is validated against the schema in the manifest and defaults to an empty object
+    pub fn new(module: &[u8], config: Option<Value>) -> Result<Self> {
+        let config = config.unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+        if let Some((manifest, _section_range)) = mrf_manifest::decode(module)? {
+            manifest.validate_config(&config)?;
+        }
+
+        let engine = mrf_runtime::engine()?;
+        let module = MrfModule::load(&engine, module, config.to_string())?;
+        let service = MrfService::builder()
+            .engine(engine)
+            .modules(vec![module])
+            .build();
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+
+        Ok(Self { runtime, service })
+    }
+
+    /// Pass the activity through the module
+    ///
+    /// Returns [`Verdict::Skipped`] if the manifest doesn't pass activities of its type in the direction to the module
+    pub fn run(&self, direction: ActivityDirection, activity: &Value) -> Result<Verdict> {
+        let Some(activity_type) = activity.get("type").and_then(Value::as_str) else {
+            bail!("activity is missing its type");
+        };
+
+        if !self
+            .service
+            .modules()
+            .iter()
+            .any(|module| module.handles(direction.into(), activity_type))
+        {
+            return Ok(Verdict::Skipped);
+        }
+
+        let activity = activity.to_string();
+        let outcome = self.runtime.block_on(self.service.handle(
+            direction.into(),
+            activity_type,
+            &activity,
+        ))?;
+
+        let verdict = match outcome {
+            Outcome::Accept(Cow::Borrowed(..)) => Verdict::Accept,
+            Outcome::Accept(Cow::Owned(modified)) => {
+                Verdict::Modify(serde_json::from_str(&modified)?)
+            }
+            Outcome::Reject => Verdict::Reject,
+        };
+
+        Ok(verdict)
+    }
+}
+
+/// Run the module over a single activity and print the verdict
+pub fn run_module<W>(
+    sink: &mut W,
+    module: &[u8],
+    config: Option<Value>,
+    direction: ActivityDirection,
+    activity: &[u8],
+) -> Result<Verdict>
+where
+    W: Write,
+{
+    let activity: Value = serde_json::from_slice(activity)?;
+    let sandbox = Sandbox::new(module, config)?;
+    let verdict = sandbox.run(direction, &activity)?;
+
+    writeln!(sink, "Verdict: {verdict}")?;
+    if let Verdict::Modify(ref modified) = verdict {
+        colored_json::write_colored_json(modified, sink)?;
+        writeln!(sink)?;
+    }
+
+    Ok(verdict)
+}
+
+/// Run the module over all fixtures (files ending with `.json`) in the directory
+///
+/// The fixtures are executed in alphabetical order and share the storage of the module
+pub fn test_module<F, W>(
+    fs: &mut F,
+    sink: &mut W,
+    module: &[u8],
+    config: Option<Value>,
+    fixtures_path: &Path,
+) -> Result<()>
+where
+    F: Filesystem,
+    W: Write,
+{
+    let sandbox = Sandbox::new(module, config)?;
+
+    let mut fixture_paths: Vec<_> = fs
+        .read_dir(fixtures_path)?
+        .into_iter()
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    fixture_paths.sort();
+
+    if fixture_paths.is_empty() {
+        bail!("no fixtures found in {}", fixtures_path.display());
+    }
+
+    let mut failed = 0;
+    for fixture_path in &fixture_paths {
+        let name = fixture_path
+            .file_stem()
+            .ok_or_else(|| eyre!("invalid fixture path"))?
+            .to_string_lossy();
+
+        let fixture: Fixture = serde_json::from_slice(&fs.read(fixture_path)?)?;
+        let mismatch = match sandbox.run(fixture.direction, &fixture.activity) {
+            Ok(verdict) => fixture.check(&verdict),
+            Err(error) => Some(format!("{error:#}")),
+        };
+
+        if let Some(mismatch) = mismatch {
+            failed += 1;
+            writeln!(sink, "{name} ... FAILED: {mismatch}")?;
+        } else {
+            writeln!(sink, "{name} ... ok")?;
+        }
+    }
+
+    let passed = fixture_paths.len() - failed;
+    writeln!(sink, "\n{passed} passed, {failed} failed")?;
+
+    if failed > 0 {
+        bail!("{failed} of {} fixtures failed", fixture_paths.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use crate::{Filesystem, args::BuildModule};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail, eyre},
+};
+use mrf_manifest::Manifest;
+use serde::Deserialize;
+use std::{
+    borrow::Cow,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Target the modules are compiled for
+///
+/// We don't compile for WASI since modules may only import the interfaces declared by the MRF world
+const TARGET: &str = "wasm32-unknown-unknown";
+
+/// Files of a new project, relative to the project directory
+///
+/// `{{name}}` is replaced with the name of the module
+const TEMPLATE: &[(&str, &str)] = &[
+    (
+        "Cargo.toml",
+        include_str!("../templates/new/Cargo.toml.tmpl"),
+    ),
+    (".gitignore", include_str!("../templates/new/gitignore")),
+    (
+        "manifest.json",
+        include_str!("../templates/new/manifest.json"),
+    ),
+    (
+        "src/bin/config-schema.rs",
+        include_str!("../templates/new/src/bin/config-schema.rs"),
+    ),
+    (
+        "src/config.rs",
+        include_str!("../templates/new/src/config.rs"),
+    ),
+    ("src/lib.rs", include_str!("../templates/new/src/lib.rs")),
+    ("wit/mrf.wit", mrf_runtime::WIT),
+];
+
+#[derive(Deserialize)]
+struct CargoToml {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    version: semver::Version,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum CargoMessage {
+    CompilerArtifact {
+        filenames: Vec<PathBuf>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Check whether the name is usable as a crate name
+#[inline]
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+/// Run cargo in the project directory and return its stdout
+fn run_cargo(project_path: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+        .args(args)
+        .current_dir(project_path)
+        .stderr(Stdio::inherit())
+        .output()
+        .wrap_err("failed to run cargo")?;
+
+    if !output.status.success() {
+        bail!("cargo {} failed with {}", args.join(" "), output.status);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Turn the core module emitted by the compiler into a component
+///
+/// The type information required for this is embedded into the core module by `wit-bindgen`
+fn componentize(core_module: &[u8]) -> Result<Vec<u8>> {
+    wit_component::ComponentEncoder::default()
+        .module(core_module)
+        .and_then(|encoder| encoder.validate(true).encode())
+        .map_err(|error| eyre!("failed to create component: {error:#}"))
+}
+
+/// Fill the manifest template with the package metadata from the `Cargo.toml` and the configuration schema
+///
+/// Metadata already present in the template takes precedence over the `Cargo.toml`
+pub fn assemble_manifest(
+    template: &[u8],
+    cargo_toml: &[u8],
+    config_schema: Option<&[u8]>,
+) -> Result<Manifest<'static>> {
+    let CargoToml { package } = toml::from_slice(cargo_toml)?;
+    let config_schema = config_schema.map(serde_json::from_slice).transpose()?;
+    let mut manifest = serde_json::from_slice::<Manifest<'_>>(template)?.to_owned();
+
+    match manifest {
+        Manifest::V1(ref mut v1) => {
+            v1.version = package.version;
+            v1.config_schema = config_schema;
+        }
+        Manifest::V2(ref mut v2) => {
+            v2.version = package.version;
+            v2.config_schema = config_schema;
+
+            if v2.description.is_none() {
+                v2.description = package.description.map(Cow::Owned);
+            }
+            if v2.authors.is_empty() {
+                v2.authors = package.authors.into_iter().map(Cow::Owned).collect();
+            }
+            if v2.license.is_none() {
+                v2.license = package.license.map(Cow::Owned);
+            }
+            if v2.homepage.is_none() {
+                v2.homepage = package.homepage.map(Cow::Owned);
+            }
+        }
+        _ => bail!("unsupported manifest version"),
+    }
+
+    Ok(manifest)
+}
+
+/// Create a new MRF module project
+pub fn new_project<F>(fs: &mut F, name: &str, project_path: &Path) -> Result<()>
+where
+    F: Filesystem,
+{
+    if !is_valid_name(name) {
+        bail!("invalid module name (has to be a valid crate name)");
+    }
+
+    if fs.exists(project_path) {
+        bail!("destination already exists");
+    }
+
+    for (file_path, content) in TEMPLATE {
+        let file_path = project_path.join(file_path);
+        if let Some(parent) = file_path.parent() {
+            fs.create_dir_all(parent)?;
+        }
+
+        let mut file = fs.create_or_truncate(&file_path)?;
+        file.write_all(content.replace("{{name}}", name).as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Build an MRF module project and embed its manifest
+pub fn build_project<F, W>(fs: &mut F, sink: &mut W, args: &BuildModule) -> Result<()>
+where
+    F: Filesystem,
+    W: Write,
+{
+    let cargo_toml = fs.read(&args.path.join("Cargo.toml"))?;
+    let template = fs.read(&args.path.join("manifest.json"))?;
+
+    let config_schema = run_cargo(&args.path, &["run", "--quiet", "--bin", "config-schema"])?;
+    let manifest = assemble_manifest(&template, &cargo_toml, Some(&config_schema))?;
+
+    let mut build_args = vec![
+        "build",
+        "--lib",
+        "--target",
+        TARGET,
+        "--message-format=json-render-diagnostics",
+    ];
+    if args.release {
+        build_args.push("--release");
+    }
+
+    let messages = run_cargo(&args.path, &build_args)?;
+    let core_module_path = serde_json::Deserializer::from_slice(&messages)
+        .into_iter::<CargoMessage>()
+        .filter_map(|message| match message {
+            Ok(CargoMessage::CompilerArtifact { filenames }) => Some(Ok(filenames)),
+            Ok(CargoMessage::Other) => None,
+            Err(error) => Some(Err(error)),
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .find(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "wasm")
+        })
+        .ok_or_else(|| eyre!("cargo didn't produce a WASM module"))?;
+
+    let core_module = fs.read(&core_module_path)?;
+    let component = componentize(&core_module)?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| core_module_path.with_extension("mrf.wasm"));
+
+    let mut file = fs.create_or_truncate(&output)?;
+    file.write_all(&component)?;
+    file.write_all(&mrf_manifest::encode(&manifest)?)?;
+
+    writeln!(sink, "{}", output.display())?;
+
+    Ok(())
+}
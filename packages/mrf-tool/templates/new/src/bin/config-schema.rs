@@ -0,0 +1,13 @@
+//!
+//! Prints the JSON schema of the configuration
+//!
+//! Invoked by `mrf-tool build` to embed the schema into the manifest
+//!
+
+#[path = "../config.rs"]
+mod config;
+
+fn main() {
+    let schema = schemars::schema_for!(config::Config);
+    println!("{}", serde_json::to_string(&schema).unwrap());
+}
@@ -0,0 +1,9 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Configuration of the module
+///
+/// The JSON schema of this struct is embedded into the manifest by `mrf-tool build`
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Config {}
@@ -0,0 +1,29 @@
+use self::{
+    config::Config,
+    fep::mrf::logging::{self, Level},
+};
+
+mod config;
+
+wit_bindgen::generate!({
+    path: "wit",
+    world: "mrf-v1",
+});
+
+struct Module;
+
+impl Guest for Module {
+    fn transform(configuration: String, _direction: Direction, _activity: String) -> Verdict {
+        let _config: Config = match serde_json::from_str(&configuration) {
+            Ok(config) => config,
+            Err(error) => {
+                logging::log(Level::Error, &format!("invalid configuration: {error}"));
+                return Verdict::Accept;
+            }
+        };
+
+        Verdict::Accept
+    }
+}
+
+export!(Module);
@@ -0,0 +1,75 @@
+use mrf_manifest::{ConfigError, Manifest};
+use mrf_tool::DummyFs;
+use serde_json::json;
+use std::io;
+
+const MANIFEST: &str = include_str!("test-manifest.json");
+
+fn module_with_schema() -> Vec<u8> {
+    let Manifest::V1(mut manifest) = serde_json::from_str(MANIFEST).unwrap() else {
+        panic!("unexpected manifest version");
+    };
+    manifest.config_schema = Some(
+        serde_json::from_value(json!({
+            "type": "object",
+            "properties": {
+                "blockedDomains": {
+                    "type": "array",
+                    "items": { "type": "string" }
+                }
+            },
+            "required": ["blockedDomains"]
+        }))
+        .unwrap(),
+    );
+
+    let mut module = wat::parse_str("(module)").unwrap();
+    module.extend(mrf_manifest::encode(&Manifest::V1(manifest)).unwrap());
+    module
+}
+
+#[test]
+fn validate() {
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), module_with_schema());
+    fs.insert(
+        "valid.toml".into(),
+        br#"blockedDomains = ["example.com"]"#.to_vec(),
+    );
+    fs.insert(
+        "valid.json".into(),
+        br#"{"blockedDomains": ["example.com"]}"#.to_vec(),
+    );
+    fs.insert(
+        "invalid.toml".into(),
+        br#"blockedDomains = ["example.com", 1]"#.to_vec(),
+    );
+
+    for config in ["valid.toml", "valid.json"] {
+        let result = mrf_tool::handle(
+            &mut fs,
+            &mut io::sink(),
+            ["mrf-tool", "config", "validate", "module.wasm", config],
+        );
+        assert!(result.is_ok(), "{result:?}");
+    }
+
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "config",
+            "validate",
+            "module.wasm",
+            "invalid.toml",
+        ],
+    )
+    .unwrap_err();
+
+    let Some(ConfigError::Violations(violations)) = error.downcast_ref() else {
+        panic!("unexpected error: {error:?}");
+    };
+    assert_eq!(violations.0.len(), 1);
+    assert_eq!(violations.0[0].path, "/blockedDomains/1");
+}
@@ -31,7 +31,7 @@ fn add() {
     fs.insert("module.wasm".into(), empty.clone());
     fs.insert("manifest.json".into(), MANIFEST.into());
 
-    mrf_tool::write_manifest(&mut fs, MANIFEST.as_ref(), "module.wasm".as_ref()).unwrap();
+    mrf_tool::write_manifest(&mut fs, MANIFEST.as_ref(), "module.wasm".as_ref(), false).unwrap();
     assert_eq!(
         *fs.get(Path::new("module.wasm")).unwrap(),
         module_with_manifest
@@ -60,6 +60,61 @@ fn add() {
     );
 }
 
+#[test]
+fn add_existing() {
+    let module_with_manifest = module_with_manifest();
+    let section = mrf_manifest::encode(&serde_json::from_str(MANIFEST).unwrap()).unwrap();
+
+    let mut duplicate_manifest = module_with_manifest.clone();
+    duplicate_manifest.extend(&section);
+
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), module_with_manifest.clone());
+    fs.insert("duplicate.wasm".into(), duplicate_manifest);
+    fs.insert("manifest.json".into(), MANIFEST.into());
+
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "manifest",
+            "add",
+            "manifest.json",
+            "module.wasm",
+            "--output",
+            "module.manifest.wasm",
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "module already contains a manifest (pass `--replace` to replace it)"
+    );
+
+    for module in ["module.wasm", "duplicate.wasm"] {
+        mrf_tool::handle(
+            &mut fs,
+            &mut io::sink(),
+            [
+                "mrf-tool",
+                "manifest",
+                "add",
+                "manifest.json",
+                module,
+                "--output",
+                "module.manifest.wasm",
+                "--replace",
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            *fs.get(Path::new("module.manifest.wasm")).unwrap(),
+            module_with_manifest
+        );
+    }
+}
+
 #[test]
 fn read() {
     let manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
@@ -142,6 +197,26 @@ fn remove() {
     assert_eq!(*fs.get(Path::new("module.removed.wasm")).unwrap(), empty);
 }
 
+#[test]
+fn remove_duplicates() {
+    let mut module = module_with_manifest();
+    module.extend(mrf_manifest::encode(&serde_json::from_str(MANIFEST).unwrap()).unwrap());
+
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), module);
+
+    mrf_tool::remove_manifest(
+        &mut fs,
+        "module.wasm".as_ref(),
+        "module.removed.wasm".as_ref(),
+    )
+    .unwrap();
+    assert_eq!(
+        *fs.get(Path::new("module.removed.wasm")).unwrap(),
+        empty_module()
+    );
+}
+
 #[test]
 fn remove_errors() {
     let mut fs = DummyFs::default();
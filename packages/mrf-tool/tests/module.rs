@@ -1,6 +1,32 @@
 use mrf_tool::DummyFs;
 use std::io;
 
+const MANIFEST: &str = include_str!("test-manifest.json");
+
+const TRANSFORM: &str = r#"
+    (core module $m
+        (memory (export "memory") 1)
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32) (i32.const 1024))
+        (func (export "transform") (param i32 i32 i32 i32 i32) (result i32)
+            (i32.store8 (i32.const 16) (i32.const 0))
+            (i32.const 16)
+        )
+    )
+    (core instance $i (instantiate $m))
+
+    (type $direction' (enum "incoming" "outgoing"))
+    (export $direction "direction" (type $direction'))
+    (type $verdict' (variant (case "accept") (case "reject") (case "modify" string)))
+    (export $verdict "verdict" (type $verdict'))
+
+    (func $transform
+        (param "configuration" string) (param "direction" $direction) (param "activity" string)
+        (result $verdict)
+        (canon lift (core func $i "transform") (memory $i "memory") (realloc (func $i "realloc")))
+    )
+    (export "transform" (func $transform))
+"#;
+
 #[test]
 fn validate() {
     let mut fs = DummyFs::default();
@@ -22,3 +48,89 @@ fn validate() {
     .unwrap_err();
     assert!(error.is::<wasmparser::BinaryReaderError>());
 }
+
+#[test]
+fn validate_conformance() {
+    let manifest = mrf_manifest::encode(&serde_json::from_str(MANIFEST).unwrap()).unwrap();
+
+    let mut conforming = wat::parse_str(format!("(component {TRANSFORM})")).unwrap();
+    conforming.extend(&manifest);
+
+    let mut undeclared_import = wat::parse_str(format!(
+        r#"(component
+            (import "wasi:cli/environment@0.2.0" (instance
+                (export "get-arguments" (func (result (list string))))
+            ))
+            {TRANSFORM}
+        )"#
+    ))
+    .unwrap();
+    undeclared_import.extend(&manifest);
+
+    let mut fs = DummyFs::default();
+    fs.insert("conforming.wasm".into(), conforming);
+    fs.insert("undeclared_import.wasm".into(), undeclared_import);
+
+    let result = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        ["mrf-tool", "module", "validate", "conforming.wasm"],
+    );
+    assert!(result.is_ok(), "{result:?}");
+
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        ["mrf-tool", "module", "validate", "undeclared_import.wasm"],
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref::<mrf_runtime::Error>(),
+        Some(mrf_runtime::Error::Conformance(..))
+    ));
+}
+
+#[test]
+fn inspect_module() {
+    let module = wat::parse_str(
+        r#"(module
+            (import "env" "log" (func (param i32)))
+            (memory (export "memory") 1 16)
+            (func (export "run"))
+            (@custom "test-section" "data")
+        )"#,
+    )
+    .unwrap();
+
+    let inspection = mrf_tool::inspect(&module).unwrap();
+    insta::assert_json_snapshot!(inspection);
+}
+
+#[test]
+fn inspect_component() {
+    let mut component = wat::parse_str(format!("(component {TRANSFORM})")).unwrap();
+    component.extend(mrf_manifest::encode(&serde_json::from_str(MANIFEST).unwrap()).unwrap());
+
+    let mut fs = DummyFs::default();
+    fs.insert("component.wasm".into(), component);
+
+    let mut output = Vec::new();
+    mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        ["mrf-tool", "module", "inspect", "--json", "component.wasm"],
+    )
+    .unwrap();
+
+    let inspection: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    insta::assert_json_snapshot!(inspection);
+
+    let mut output = Vec::new();
+    mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        ["mrf-tool", "module", "inspect", "component.wasm"],
+    )
+    .unwrap();
+    insta::assert_snapshot!(String::from_utf8(output).unwrap());
+}
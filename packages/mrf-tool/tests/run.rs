@@ -0,0 +1,237 @@
+use mrf_manifest::{ActivitySet, ApiVersion, Manifest, ManifestV1};
+use mrf_tool::{ActivityDirection, DummyFs, Verdict};
+use serde_json::json;
+use std::{borrow::Cow, path::Path, str};
+
+/// Component rejecting outgoing activities and replacing incoming ones with its configuration
+const COMPONENT: &str = r#"
+(component
+    (core module $m
+        (memory (export "memory") 1)
+        (global $heap (mut i32) (i32.const 1024))
+
+        (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+            (local.get $ptr)
+        )
+        (func (export "transform")
+            (param $config_ptr i32) (param $config_len i32)
+            (param $direction i32)
+            (param $activity_ptr i32) (param $activity_len i32)
+            (result i32)
+
+            (if (local.get $direction)
+                (then (i32.store8 (i32.const 16) (i32.const 1)))
+                (else
+                    (i32.store8 (i32.const 16) (i32.const 2))
+                    (i32.store (i32.const 20) (local.get $config_ptr))
+                    (i32.store (i32.const 24) (local.get $config_len))
+                )
+            )
+            (i32.const 16)
+        )
+    )
+    (core instance $i (instantiate $m))
+
+    (type $direction' (enum "incoming" "outgoing"))
+    (export $direction "direction" (type $direction'))
+    (type $verdict' (variant (case "accept") (case "reject") (case "modify" string)))
+    (export $verdict "verdict" (type $verdict'))
+
+    (func $transform
+        (param "configuration" string) (param "direction" $direction) (param "activity" string)
+        (result $verdict)
+        (canon lift (core func $i "transform") (memory $i "memory") (realloc (func $i "realloc")))
+    )
+    (export "transform" (func $transform))
+)
+"#;
+
+fn module() -> Vec<u8> {
+    module_from(COMPONENT)
+}
+
+fn module_from(component: &str) -> Vec<u8> {
+    let manifest = Manifest::V1(ManifestV1 {
+        api_version: ApiVersion::V1,
+        name: Cow::Borrowed("replace-with-config"),
+        version: semver::Version::new(1, 0, 0),
+        activity_types: ActivitySet(["Create".into()].into()),
+        config_schema: None,
+    });
+
+    let mut module = wat::parse_str(component).unwrap();
+    module.extend(mrf_manifest::encode(&manifest).unwrap());
+    module
+}
+
+fn fs() -> DummyFs {
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), module());
+    fs.insert("config.toml".into(), br#"type = "Note""#.to_vec());
+    fs.insert(
+        "create.json".into(),
+        br#"{"type": "Create", "object": "https://example.com/notes/1"}"#.to_vec(),
+    );
+    fs
+}
+
+#[test]
+fn run() {
+    let mut fs = fs();
+    let module = module();
+    let activity = fs[Path::new("create.json")].clone();
+
+    let mut output = Vec::new();
+    let verdict = mrf_tool::run_module(
+        &mut output,
+        &module,
+        Some(json!({ "type": "Note" })),
+        ActivityDirection::Incoming,
+        &activity,
+    )
+    .unwrap();
+    assert_eq!(verdict, Verdict::Modify(json!({ "type": "Note" })));
+    assert!(
+        str::from_utf8(&output)
+            .unwrap()
+            .starts_with("Verdict: modify\n")
+    );
+
+    let mut output = Vec::new();
+    mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        [
+            "mrf-tool",
+            "module",
+            "run",
+            "module.wasm",
+            "--config",
+            "config.toml",
+            "--activity",
+            "create.json",
+            "--direction",
+            "outgoing",
+        ],
+    )
+    .unwrap();
+    assert_eq!(output, b"Verdict: reject\n");
+}
+
+#[test]
+fn run_filtered() {
+    let mut output = Vec::new();
+    let verdict = mrf_tool::run_module(
+        &mut output,
+        &module(),
+        None,
+        ActivityDirection::Incoming,
+        br#"{"type": "Like"}"#,
+    )
+    .unwrap();
+    assert_eq!(verdict, Verdict::Skipped);
+    assert_eq!(output, b"Verdict: skipped (filtered)\n");
+}
+
+#[test]
+fn run_out_of_fuel() {
+    let component = COMPONENT.replace(
+        "(if (local.get $direction)",
+        "(loop $spin (br $spin))\n            (if (local.get $direction)",
+    );
+
+    let error = mrf_tool::run_module(
+        &mut Vec::new(),
+        &module_from(&component),
+        None,
+        ActivityDirection::Incoming,
+        br#"{"type": "Create"}"#,
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "module exceeded its fuel budget");
+}
+
+#[test]
+fn test_fixtures() {
+    let mut fs = fs();
+    fs.insert(
+        "fixtures/filtered.json".into(),
+        serde_json::to_vec(&json!({
+            "activity": { "type": "Like" },
+            "verdict": "skipped",
+        }))
+        .unwrap(),
+    );
+    fs.insert(
+        "fixtures/modify.json".into(),
+        serde_json::to_vec(&json!({
+            "activity": { "type": "Create" },
+            "verdict": "modify",
+            "modified": { "type": "Note" },
+        }))
+        .unwrap(),
+    );
+    fs.insert(
+        "fixtures/reject.json".into(),
+        serde_json::to_vec(&json!({
+            "direction": "outgoing",
+            "activity": { "type": "Create" },
+            "verdict": "reject",
+        }))
+        .unwrap(),
+    );
+
+    let mut output = Vec::new();
+    mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        [
+            "mrf-tool",
+            "module",
+            "test",
+            "module.wasm",
+            "fixtures",
+            "--config",
+            "config.toml",
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        str::from_utf8(&output).unwrap(),
+        "filtered ... ok\nmodify ... ok\nreject ... ok\n\n3 passed, 0 failed\n"
+    );
+
+    fs.insert(
+        "fixtures/wrong.json".into(),
+        serde_json::to_vec(&json!({
+            "activity": { "type": "Create" },
+            "verdict": "accept",
+        }))
+        .unwrap(),
+    );
+
+    let mut output = Vec::new();
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        [
+            "mrf-tool",
+            "module",
+            "test",
+            "module.wasm",
+            "fixtures",
+            "--config",
+            "config.toml",
+        ],
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "1 of 4 fixtures failed");
+    assert!(
+        str::from_utf8(&output)
+            .unwrap()
+            .contains("wrong ... FAILED: expected verdict accept, got modify\n")
+    );
+}
@@ -0,0 +1,104 @@
+use mrf_manifest::Manifest;
+use mrf_tool::{DummyFs, NativeFs};
+use std::{fs, io, path::Path};
+
+#[test]
+fn new() {
+    let mut fs = DummyFs::default();
+
+    let result = mrf_tool::handle(&mut fs, &mut io::sink(), ["mrf-tool", "new", "my-filter"]);
+    assert!(result.is_ok(), "{result:?}");
+
+    let cargo_toml = String::from_utf8(fs[Path::new("my-filter/Cargo.toml")].clone()).unwrap();
+    assert!(cargo_toml.contains(r#"name = "my-filter""#));
+
+    let manifest: Manifest<'_> =
+        serde_json::from_slice(&fs[Path::new("my-filter/manifest.json")]).unwrap();
+    assert_eq!(manifest.upgrade().name, "my-filter");
+
+    assert!(fs.contains_key(Path::new("my-filter/src/lib.rs")));
+    assert!(fs.contains_key(Path::new("my-filter/wit/mrf.wit")));
+
+    let result = mrf_tool::handle(&mut fs, &mut io::sink(), ["mrf-tool", "new", "my-filter"]);
+    assert!(result.is_err());
+
+    let result = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        ["mrf-tool", "new", "0-invalid name"],
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn assemble_manifest() {
+    const TEMPLATE: &str = include_str!("../templates/new/manifest.json");
+    const CARGO_TOML: &str = r#"
+        [package]
+        name = "my-filter"
+        version = "1.2.3"
+        description = "Filters things"
+        license = "MIT"
+    "#;
+    const SCHEMA: &str = r#"{"type": "object"}"#;
+
+    let manifest = mrf_tool::assemble_manifest(
+        TEMPLATE.as_bytes(),
+        CARGO_TOML.as_bytes(),
+        Some(SCHEMA.as_bytes()),
+    )
+    .unwrap();
+
+    let Manifest::V2(manifest) = manifest else {
+        panic!("unexpected manifest version");
+    };
+    assert_eq!(manifest.version, semver::Version::new(1, 2, 3));
+    assert_eq!(manifest.description.as_deref(), Some("Filters things"));
+    assert_eq!(manifest.license.as_deref(), Some("MIT"));
+    assert!(manifest.config_schema.is_some());
+}
+
+#[test]
+fn build_scaffold() {
+    let base_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("scaffold");
+    let project_path = base_path.join("my-filter");
+    let output_path = base_path.join("my-filter.mrf.wasm");
+    if base_path.exists() {
+        fs::remove_dir_all(&base_path).unwrap();
+    }
+    fs::create_dir_all(&base_path).unwrap();
+
+    let mut fs = NativeFs::default();
+    let result = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "new",
+            "my-filter",
+            "--path",
+            project_path.to_str().unwrap(),
+        ],
+    );
+    assert!(result.is_ok(), "{result:?}");
+
+    let result = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "build",
+            "--path",
+            project_path.to_str().unwrap(),
+            "--output",
+            output_path.to_str().unwrap(),
+        ],
+    );
+    assert!(result.is_ok(), "{result:?}");
+
+    let module = fs::read(&output_path).unwrap();
+    let (manifest, _section_range) = mrf_manifest::decode(&module).unwrap().unwrap();
+    let manifest = manifest.upgrade();
+    assert_eq!(manifest.name, "my-filter");
+    assert!(manifest.config_schema.is_some());
+}
@@ -0,0 +1,124 @@
+use mrf_manifest::{Manifest, SignatureError};
+use mrf_tool::DummyFs;
+use std::{io, path::Path};
+
+const KEY: &str = include_str!("test-key.pem");
+const MANIFEST: &str = include_str!("test-manifest.json");
+const TRUST_STORE: &str = include_str!("test-trust-store.toml");
+
+fn module_with_manifest() -> Vec<u8> {
+    let manifest: Manifest<'_> = serde_json::from_str(MANIFEST).unwrap();
+    let mut module = wat::parse_str("(module)").unwrap();
+    module.extend(mrf_manifest::encode(&manifest).unwrap());
+    module
+}
+
+#[test]
+fn sign_and_verify() {
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), module_with_manifest());
+    fs.insert("key.pem".into(), KEY.into());
+    fs.insert("trust-store.toml".into(), TRUST_STORE.into());
+
+    mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "sign",
+            "module.wasm",
+            "--key",
+            "key.pem",
+            "--key-id",
+            "test",
+            "--output",
+            "module.signed.wasm",
+        ],
+    )
+    .unwrap();
+
+    let (signature, _section_range) =
+        mrf_manifest::decode_signature(&fs[Path::new("module.signed.wasm")])
+            .unwrap()
+            .unwrap();
+    assert_eq!(signature.key_id, "test");
+
+    let mut output = Vec::new();
+    mrf_tool::handle(
+        &mut fs,
+        &mut output,
+        [
+            "mrf-tool",
+            "verify",
+            "module.signed.wasm",
+            "--trust-store",
+            "trust-store.toml",
+        ],
+    )
+    .unwrap();
+    assert_eq!(output, b"module signed by trusted key \"test\"\n");
+
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "verify",
+            "module.wasm",
+            "--trust-store",
+            "trust-store.toml",
+        ],
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SignatureError::MissingSignature)
+    ));
+}
+
+#[test]
+fn sign_errors() {
+    let mut fs = DummyFs::default();
+    fs.insert("module.wasm".into(), wat::parse_str("(module)").unwrap());
+    fs.insert("key.pem".into(), KEY.into());
+    fs.insert("garbage.pem".into(), b"garbage".to_vec());
+
+    let error = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "sign",
+            "module.wasm",
+            "--key",
+            "key.pem",
+            "--key-id",
+            "test",
+            "--output",
+            "module.signed.wasm",
+        ],
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SignatureError::MissingManifest)
+    ));
+
+    fs.insert("module.wasm".into(), module_with_manifest());
+    let result = mrf_tool::handle(
+        &mut fs,
+        &mut io::sink(),
+        [
+            "mrf-tool",
+            "sign",
+            "module.wasm",
+            "--key",
+            "garbage.pem",
+            "--key-id",
+            "test",
+            "--output",
+            "module.signed.wasm",
+        ],
+    );
+    assert!(result.is_err());
+}
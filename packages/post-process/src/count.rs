@@ -0,0 +1,73 @@
+//!
+//! Counting the characters of a post
+//!
+
+use crate::{Element, link, tokens};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Rules for counting the characters of a post
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CountingPolicy {
+    /// Number of characters every link with a scheme counts as, regardless of its actual length
+    ///
+    /// `None` counts links like any other text.
+    /// Links without a scheme, such as `www.example.com`, are always counted like any other text, the way Mastodon does it
+    pub link_length: Option<usize>,
+
+    /// Whether the domain of a remote mention counts towards the length
+    pub count_mention_domains: bool,
+}
+
+impl CountingPolicy {
+    /// Policy used by Mastodon
+    ///
+    /// Links with a scheme count as 23 characters and remote mentions only count their username
+    pub const MASTODON: Self = Self {
+        link_length: Some(23),
+        count_mention_domains: false,
+    };
+
+    /// Policy counting every character of the post
+    pub const VERBATIM: Self = Self {
+        link_length: None,
+        count_mention_domains: true,
+    };
+}
+
+impl Default for CountingPolicy {
+    fn default() -> Self {
+        Self::MASTODON
+    }
+}
+
+#[inline]
+fn graphemes(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Count the characters of a plain text post according to the policy
+///
+/// Characters are counted as extended grapheme clusters, so an emoji made up of multiple code points counts as one
+#[must_use]
+pub fn count_characters(text: &str, policy: CountingPolicy) -> usize {
+    let mut count = 0;
+    let mut last_end = 0;
+
+    for (element, span) in tokens(text) {
+        count += graphemes(&text[last_end..span.start]);
+        last_end = span.end;
+
+        count += match (element, policy.link_length) {
+            (Element::Link(link), Some(link_length)) if !link::is_schemeless(&link.content) => {
+                link_length
+            }
+            (Element::Mention(mention), _) if !policy.count_mention_domains => {
+                // Leading `@`
+                1 + graphemes(&mention.username)
+            }
+            _ => graphemes(&text[span]),
+        };
+    }
+
+    count + graphemes(&text[last_end..])
+}
@@ -0,0 +1,99 @@
+//!
+//! Encoding of remote emote domains into shortcodes
+//!
+//! Other software (like Mastodon or Misskey) only accepts ASCII letters, digits and underscores in shortcodes,
+//! so remote emotes are rendered as `:shortcode__R1_domain:` with the domain encoded as follows:
+//!
+//! - letters are lowercased, letters and digits are kept as they are
+//! - `.` is encoded as `_`
+//! - `-` is encoded as `H`
+//! - `_` is encoded as `U`
+//!
+//! Internationalised domains are converted into punycode first.
+//!
+//! The `__R1_` marker separates the shortcode from the encoded domain. `R` marks a remote emote and `1` the version of the encoding.
+//! Shortcodes rendered by earlier versions (`:shortcode__domain:` with both `.` and `-` replaced by `_`) can't be decoded unambiguously,
+//! so they don't carry the marker and are read as local shortcodes, the same way they were before.
+//!
+//! The encoded domain never contains an uppercase `R`, which makes the last marker of a shortcode the separator,
+//! even if the shortcode itself contains the marker, and lets every encoded shortcode be mapped back to exactly one shortcode and domain.
+//! Local shortcodes containing the marker are reserved (see [`is_reserved`]) since they would be read back as remote ones.
+//!
+
+use crate::link;
+use std::borrow::Cow;
+
+/// Versioned marker separating the shortcode from the encoded domain
+const MARKER: &str = "__R1_";
+
+/// Real domains consist of at least two labels, none of them empty
+fn is_valid(domain: &str) -> bool {
+    domain.contains('.') && !domain.split('.').any(str::is_empty)
+}
+
+/// Encode a domain so it can be embedded into a shortcode
+///
+/// Returns `None` if the domain has less than two labels, empty labels or characters that aren't allowed in domains
+pub fn encode_domain(domain: &str) -> Option<String> {
+    let domain = link::host_to_ascii(domain);
+    if !is_valid(&domain) {
+        return None;
+    }
+
+    domain
+        .chars()
+        .map(|c| match c {
+            '.' => Some('_'),
+            '-' => Some('H'),
+            '_' => Some('U'),
+            c if c.is_ascii_alphanumeric() => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Decode a domain encoded by [`encode_domain`]
+///
+/// Returns `None` if the input isn't an encoded domain
+pub fn decode_domain(encoded: &str) -> Option<String> {
+    let domain: String = encoded
+        .chars()
+        .map(|c| match c {
+            '_' => Some('.'),
+            'H' => Some('-'),
+            'U' => Some('_'),
+            c if c.is_ascii_lowercase() || c.is_ascii_digit() => Some(c),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    is_valid(&domain).then_some(domain)
+}
+
+/// Check whether a shortcode is reserved for remote emotes and therefore can't be used by a local one
+pub fn is_reserved(shortcode: &str) -> bool {
+    shortcode.contains(MARKER)
+}
+
+/// Split a shortcode into the shortcode and domain of a remote emote, if it contains an encoded domain
+pub fn split_shortcode(shortcode: &str) -> Option<(&str, String)> {
+    let (shortcode, encoded) = shortcode.rsplit_once(MARKER)?;
+    if shortcode.is_empty() {
+        return None;
+    }
+
+    decode_domain(encoded).map(|domain| (shortcode, domain))
+}
+
+/// Render the shortcode of an emote, encoding its domain if it is a remote one
+///
+/// Domains that can't be encoded are appended with an `@` instead, the way they are written in posts
+pub fn render_shortcode<'a>(shortcode: &'a str, domain: Option<&str>) -> Cow<'a, str> {
+    match domain {
+        Some(domain) => match encode_domain(domain) {
+            Some(encoded) => Cow::Owned(format!("{shortcode}{MARKER}{encoded}")),
+            None => Cow::Owned(format!("{shortcode}@{domain}")),
+        },
+        None => Cow::Borrowed(shortcode),
+    }
+}
@@ -0,0 +1,117 @@
+//!
+//! Extraction of the emotes, hashtags, links and mentions contained in a post
+//!
+
+use crate::{Element, Emote, Hashtag, Link, Mention, tokens};
+use logos::Span;
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    hash::Hash,
+};
+
+/// Value found in a post, together with all the places it was found at
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Occurrence<T> {
+    /// Value of the first occurrence
+    pub value: T,
+
+    /// Spans of all occurrences, in the order they appear in
+    pub spans: Vec<Span>,
+}
+
+/// De-duplicated elements of a post, in the order of their first occurrence
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Extracted<'a> {
+    /// Emotes, compared by shortcode and domain
+    pub emotes: Vec<Occurrence<Emote<'a>>>,
+
+    /// Hashtags, compared by their normalised key
+    pub hashtags: Vec<Occurrence<Hashtag<'a>>>,
+
+    /// Links, compared verbatim
+    pub links: Vec<Occurrence<Link<'a>>>,
+
+    /// Mentions, compared case-insensitively by username and domain
+    pub mentions: Vec<Occurrence<Mention<'a>>>,
+}
+
+/// Occurrences of one kind of value, de-duplicated by a key
+struct Deduplicated<K, T> {
+    indices: HashMap<K, usize>,
+    occurrences: Vec<Occurrence<T>>,
+}
+
+impl<K, T> Default for Deduplicated<K, T> {
+    fn default() -> Self {
+        Self {
+            indices: HashMap::new(),
+            occurrences: Vec::new(),
+        }
+    }
+}
+
+impl<K, T> Deduplicated<K, T>
+where
+    K: Eq + Hash,
+{
+    fn insert(&mut self, key: K, value: T, span: Span) {
+        match self.indices.entry(key) {
+            Entry::Occupied(entry) => self.occurrences[*entry.get()].spans.push(span),
+            Entry::Vacant(entry) => {
+                entry.insert(self.occurrences.len());
+                self.occurrences.push(Occurrence {
+                    value,
+                    spans: vec![span],
+                });
+            }
+        }
+    }
+}
+
+#[inline]
+fn lowercase_domain(domain: Option<&str>) -> Option<String> {
+    domain.map(str::to_ascii_lowercase)
+}
+
+/// Extract the emotes, hashtags, links and mentions of a plain text post without rendering it
+///
+/// Unlike [`transform`](crate::transform), this doesn't need an async runtime
+#[must_use]
+pub fn extract(text: &str) -> Extracted<'_> {
+    let mut emotes = Deduplicated::default();
+    let mut hashtags = Deduplicated::default();
+    let mut links = Deduplicated::default();
+    let mut mentions = Deduplicated::default();
+
+    for (element, span) in tokens(text) {
+        match element {
+            Element::Emote(emote) => {
+                let key = (
+                    emote.shortcode.to_string(),
+                    lowercase_domain(emote.domain.as_deref()),
+                );
+                emotes.insert(key, emote, span);
+            }
+            Element::Hashtag(hashtag) => hashtags.insert(hashtag.key.to_string(), hashtag, span),
+            Element::Link(link) => links.insert(link.content.to_string(), link, span),
+            Element::Mention(mention) => {
+                let key = (
+                    mention.username.to_lowercase(),
+                    lowercase_domain(mention.domain.as_deref()),
+                );
+                mentions.insert(key, mention, span);
+            }
+            Element::Formatting(..)
+            | Element::Html(..)
+            | Element::MfmFunction(..)
+            | Element::Text(..) => {}
+        }
+    }
+
+    Extracted {
+        emotes: emotes.occurrences,
+        hashtags: hashtags.occurrences,
+        links: links.occurrences,
+        mentions: mentions.occurrences,
+    }
+}
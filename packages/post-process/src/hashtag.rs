@@ -0,0 +1,83 @@
+//!
+//! Recognition and normalisation of hashtags, following the rules of Mastodon
+//!
+
+use crate::{PostElement, enforce_prefix};
+use logos::Lexer;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Characters allowed inside of a hashtag to separate words
+#[inline]
+fn is_separator(c: char) -> bool {
+    matches!(c, '_' | '\u{00B7}' | '\u{30FB}' | '\u{200C}')
+}
+
+#[inline]
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || is_combining_mark(c)
+}
+
+/// Check whether the name matches Mastodon's hashtag pattern
+///
+/// The name has to start and end with a word character and contain either a letter or a separator between two other characters.
+/// This rejects names consisting only of digits.
+fn is_valid(name: &str) -> bool {
+    let chars: Vec<char> = name.chars().collect();
+
+    let (Some(&first), Some(&last)) = (chars.first(), chars.last()) else {
+        return false;
+    };
+
+    if !is_word(first) || !is_word(last) || !chars.iter().all(|&c| is_word(c) || is_separator(c)) {
+        return false;
+    }
+
+    chars.iter().any(|c| c.is_alphabetic())
+        || chars
+            .get(1..chars.len().saturating_sub(1))
+            .is_some_and(|inner| inner.iter().copied().any(is_separator))
+}
+
+/// Lexer callback extending the matched `#` to the full hashtag
+///
+/// Hashtags directly following an alphanumeric character, `=`, `/` or `)` aren't recognised
+pub fn lex<'a>(lexer: &mut Lexer<'a, PostElement<'a>>) -> Option<&'a str> {
+    let preceding = lexer.source()[..lexer.span().start].chars().next_back();
+    if !enforce_prefix(lexer)
+        || preceding.is_some_and(|c| c.is_alphanumeric() || matches!(c, '=' | '/' | ')'))
+    {
+        return None;
+    }
+
+    // Separators at the end belong to the surrounding text, such as the `·` in `#rust·`
+    let rest = lexer.remainder();
+    let length = rest
+        .find(|c: char| !is_word(c) && !is_separator(c))
+        .unwrap_or(rest.len());
+    let length = rest[..length].trim_end_matches(|c| !is_word(c)).len();
+
+    let name = &rest[..length];
+    if !is_valid(name) {
+        return None;
+    }
+
+    lexer.bump(length);
+    Some(name)
+}
+
+/// Normalise the name of a hashtag (without the leading `#`) into the key used to compare hashtags
+///
+/// The name is NFKC-normalised and lowercased, and characters not allowed in hashtags are removed.
+/// This makes `#Rust`, `#rust` and `＃ｒｕｓｔ` share the key `rust`.
+///
+/// Returns `None` if the result isn't a valid hashtag, for example because it only consists of digits
+#[must_use]
+pub fn normalise_hashtag(name: &str) -> Option<String> {
+    let normalised: String = name
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .filter(|&c| is_word(c) || is_separator(c))
+        .collect();
+
+    is_valid(&normalised).then_some(normalised)
+}
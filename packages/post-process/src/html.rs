@@ -0,0 +1,788 @@
+//!
+//! Front-end for HTML received from remote servers, such as the content of `ActivityPub` notes
+//!
+//! Mentions, hashtags and links are recognised by the microformat conventions used by Mastodon, Misskey and others
+//! (`<a class="u-url mention">`, `<span class="h-card">`, `<a rel="tag">`).
+//! Custom emotes are recognised in the text. Formatting is mapped onto [`FormattingKind`],
+//! every other tag is dropped while its content is kept, scripts and similar are dropped entirely.
+//!
+
+use crate::{
+    Allowlist, Element, Formatting, FormattingKind, Hashtag, Link, Mention, Render, Result, Text,
+    tokens, transform_elements,
+};
+use std::{borrow::Cow, mem, ops::Deref, vec};
+
+/// Maximum nesting depth of tags
+///
+/// Tags nested deeper are flattened into their closest ancestor within the limit, only their content is kept.
+/// This is higher than the limit of the MFM front-end since wrapping tags such as `<span>` count as well.
+const MAX_DEPTH: usize = 32;
+
+/// Tags that never have any content
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Tags whose content is raw text instead of markup
+const RAW_TEXT_TAGS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Tags that are dropped together with their content
+const DROPPED_TAGS: &[&str] = &[
+    "embed", "head", "iframe", "math", "noscript", "object", "script", "style", "svg", "template",
+    "textarea", "title",
+];
+
+/// Tags implicitly closing an open paragraph
+const CLOSES_PARAGRAPH: &[&str] = &[
+    "blockquote",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ol",
+    "p",
+    "pre",
+    "ul",
+];
+
+/// Parse remote HTML into a tree of elements
+#[must_use]
+pub fn parse(html: &str) -> Vec<Element<'_>> {
+    let nodes = Tokenizer { html, pos: 0 }.into_tree();
+    convert(nodes)
+}
+
+/// Transform remote HTML and render it into sanitised HTML
+///
+/// There is no variant without sanitisation since remote HTML can't be trusted.
+/// See [`crate::transform_safe`].
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_safe<'a, F, Fut>(
+    html: &'a str,
+    transformer: F,
+    allowlist: &Allowlist<'_>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let mut elements = parse(html);
+    transform_elements(&mut elements, transformer).await?;
+
+    let mut out = String::with_capacity(html.len());
+    for element in &elements {
+        element.render_safe(&mut out, allowlist);
+    }
+
+    Ok(out)
+}
+
+/// Decode the character references contained in the text
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        decoded.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        // References are short, so only their maximum length is searched for the terminating semicolon
+        let reference = rest.as_bytes()[1..]
+            .iter()
+            .take(33)
+            .position(|&byte| byte == b';')
+            .and_then(|end| Some((decode_reference(&rest[1..=end])?, end + 2)));
+
+        if let Some((character, len)) = reference {
+            decoded.push(character);
+            rest = &rest[len..];
+        } else {
+            decoded.push('&');
+            rest = &rest[1..];
+        }
+    }
+    decoded.push_str(rest);
+
+    Cow::Owned(decoded)
+}
+
+fn decode_reference(reference: &str) -> Option<char> {
+    if let Some(number) = reference.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+
+        return char::from_u32(code).filter(|&character| character != '\0');
+    }
+
+    let character = match reference {
+        "amp" => '&',
+        "apos" => '\'',
+        "gt" => '>',
+        "hellip" => '…',
+        "lt" => '<',
+        "nbsp" => '\u{a0}',
+        "quot" => '"',
+        _ => return None,
+    };
+
+    Some(character)
+}
+
+#[derive(Debug)]
+enum Node<'a> {
+    Tag {
+        name: String,
+        attributes: Vec<(String, Cow<'a, str>)>,
+        children: Children<'a>,
+    },
+    Text(Cow<'a, str>),
+}
+
+/// Children of a tag
+///
+/// Dropping them is iterative, so deeply nested trees can't overflow the stack
+#[derive(Debug, Default)]
+struct Children<'a>(Vec<Node<'a>>);
+
+impl<'a> Deref for Children<'a> {
+    type Target = [Node<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> IntoIterator for Children<'a> {
+    type Item = Node<'a>;
+    type IntoIter = vec::IntoIter<Node<'a>>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        mem::take(&mut self.0).into_iter()
+    }
+}
+
+impl Drop for Children<'_> {
+    fn drop(&mut self) {
+        let mut nodes = mem::take(&mut self.0);
+        while let Some(node) = nodes.pop() {
+            if let Node::Tag { mut children, .. } = node {
+                nodes.append(&mut children.0);
+            }
+        }
+    }
+}
+
+impl<'a> Node<'a> {
+    fn attribute(&self, name: &str) -> Option<&Cow<'a, str>> {
+        match self {
+            Self::Tag { attributes, .. } => attributes
+                .iter()
+                .find(|(attribute, _)| attribute == name)
+                .map(|(_, value)| value),
+            Self::Text(..) => None,
+        }
+    }
+
+    fn has_class(&self, class: &str) -> bool {
+        self.attribute("class")
+            .is_some_and(|classes| classes.split_ascii_whitespace().any(|value| value == class))
+    }
+
+    fn has_rel(&self, rel: &str) -> bool {
+        self.attribute("rel")
+            .is_some_and(|rels| rels.split_ascii_whitespace().any(|value| value == rel))
+    }
+}
+
+/// Concatenated text of the nodes, as displayed
+fn visible_text<'a>(nodes: &[Node<'a>]) -> Cow<'a, str> {
+    fn collect(nodes: &[Node<'_>], out: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Tag { name, .. } if name == "br" => out.push('\n'),
+                Node::Tag { name, .. } if DROPPED_TAGS.contains(&name.as_str()) => {}
+                Node::Tag { children, .. } => collect(children, out),
+                Node::Text(text) => out.push_str(text),
+            }
+        }
+    }
+
+    if let [Node::Text(text)] = nodes {
+        return text.clone();
+    }
+
+    let mut out = String::new();
+    collect(nodes, &mut out);
+    Cow::Owned(out)
+}
+
+/// Apply a slicing function to the text, keeping it borrowed if possible
+fn map_cow(value: Cow<'_, str>, f: impl FnOnce(&str) -> &str) -> Cow<'_, str> {
+    match value {
+        Cow::Borrowed(value) => Cow::Borrowed(f(value)),
+        Cow::Owned(value) => Cow::Owned(f(&value).to_owned()),
+    }
+}
+
+/// Split the text at the first occurrence of the delimiter, keeping it borrowed if possible
+fn split_once(value: Cow<'_, str>, delimiter: char) -> (Cow<'_, str>, Option<Cow<'_, str>>) {
+    match value {
+        Cow::Borrowed(value) => match value.split_once(delimiter) {
+            Some((head, tail)) => (Cow::Borrowed(head), Some(Cow::Borrowed(tail))),
+            None => (Cow::Borrowed(value), None),
+        },
+        Cow::Owned(value) => match value.split_once(delimiter) {
+            Some((head, tail)) => (
+                Cow::Owned(head.to_owned()),
+                Some(Cow::Owned(tail.to_owned())),
+            ),
+            None => (Cow::Owned(value), None),
+        },
+    }
+}
+
+/// Host component of an absolute URL
+fn host(url: &str) -> Option<&str> {
+    let (_scheme, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next()?;
+
+    (!host.is_empty()).then_some(host)
+}
+
+/// Name, attributes and children of a tag that hasn't been closed yet
+type OpenTag<'a> = (String, Vec<(String, Cow<'a, str>)>, Vec<Node<'a>>);
+
+struct Tokenizer<'a> {
+    html: &'a str,
+    pos: usize,
+}
+
+enum Token<'a> {
+    Start {
+        name: String,
+        attributes: Vec<(String, Cow<'a, str>)>,
+        self_closing: bool,
+    },
+    End(String),
+    Text(&'a str),
+}
+
+impl<'a> Tokenizer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.html[self.pos..]
+    }
+
+    fn skip_past(&mut self, needle: &str) {
+        self.pos = self
+            .rest()
+            .find(needle)
+            .map_or(self.html.len(), |idx| self.pos + idx + needle.len());
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn name(&mut self) -> String {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '/' | '>' | '='))
+            .unwrap_or(rest.len());
+
+        self.pos += len;
+        rest[..len].to_ascii_lowercase()
+    }
+
+    fn attribute_value(&mut self) -> &'a str {
+        let rest = self.rest();
+        if let Some(quote) = rest.chars().next().filter(|&c| c == '"' || c == '\'') {
+            let len = rest[1..].find(quote).unwrap_or(rest.len() - 1);
+            self.pos += len + 2;
+            self.pos = self.pos.min(self.html.len());
+            return &rest[1..=len];
+        }
+
+        let len = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '>')
+            .unwrap_or(rest.len());
+
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn start_tag(&mut self) -> Token<'a> {
+        self.pos += 1;
+        let name = self.name();
+        let mut attributes = Vec::new();
+        let mut self_closing = false;
+
+        loop {
+            self.skip_whitespace();
+
+            let rest = self.rest();
+            if rest.is_empty() {
+                break;
+            } else if let Some(rest) = rest.strip_prefix('/') {
+                self_closing = rest.starts_with('>');
+                self.pos += 1;
+                continue;
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+
+            let attribute = self.name();
+            if attribute.is_empty() {
+                // Stray `=`
+                self.pos += 1;
+                continue;
+            }
+
+            self.skip_whitespace();
+            let value = if self.rest().starts_with('=') {
+                self.pos += 1;
+                self.skip_whitespace();
+                decode_entities(self.attribute_value())
+            } else {
+                Cow::Borrowed("")
+            };
+
+            if !attributes.iter().any(|(name, _)| *name == attribute) {
+                attributes.push((attribute, value));
+            }
+        }
+
+        Token::Start {
+            name,
+            attributes,
+            self_closing,
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token<'a>> {
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return None;
+            }
+
+            let mut chars = rest.chars();
+            let (first, second) = (chars.next(), chars.next());
+
+            match (first, second) {
+                (Some('<'), Some('!')) if rest.starts_with("<!--") => {
+                    self.pos += 4;
+                    self.skip_past("-->");
+                }
+                (Some('<'), Some('!' | '?')) => self.skip_past(">"),
+                (Some('<'), Some('/')) => {
+                    self.pos += 2;
+                    let name = self.name();
+                    self.skip_past(">");
+
+                    if !name.is_empty() {
+                        return Some(Token::End(name));
+                    }
+                }
+                (Some('<'), Some(c)) if c.is_ascii_alphabetic() => return Some(self.start_tag()),
+                _ => {
+                    // Text runs until the next `<` that starts markup
+                    let mut len = first.map_or(0, char::len_utf8);
+                    while let Some(idx) = rest[len..].find('<') {
+                        len += idx;
+
+                        let after = rest[len + 1..].chars().next();
+                        if after.is_some_and(|c| {
+                            c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')
+                        }) {
+                            break;
+                        }
+
+                        len += 1;
+                    }
+
+                    if !rest[len..].starts_with('<') {
+                        len = rest.len();
+                    }
+
+                    self.pos += len;
+                    return Some(Token::Text(&rest[..len]));
+                }
+            }
+        }
+    }
+
+    /// Raw text up to the end tag
+    fn raw_text(&mut self, name: &str) -> &'a str {
+        let rest = self.rest();
+
+        let mut len = 0;
+        while let Some(idx) = rest[len..].find("</") {
+            len += idx;
+
+            let candidate = rest.as_bytes()[len + 2..].get(..name.len());
+            if candidate.is_some_and(|candidate| candidate.eq_ignore_ascii_case(name.as_bytes())) {
+                break;
+            }
+
+            len += 2;
+        }
+
+        if !rest[len..].starts_with("</") {
+            len = rest.len();
+        }
+
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn into_tree(mut self) -> Vec<Node<'a>> {
+        fn close(stack: &mut Vec<OpenTag<'_>>) {
+            if let Some((name, attributes, children)) = stack.pop() {
+                let node = Node::Tag {
+                    name,
+                    attributes,
+                    children: Children(children),
+                };
+
+                if let Some((.., parent)) = stack.last_mut() {
+                    parent.push(node);
+                }
+            }
+        }
+
+        // Stack of open tags, the bottom being the root
+        let mut stack: Vec<OpenTag<'a>> = vec![(String::new(), Vec::new(), Vec::new())];
+
+        // Tags opened past the maximum depth, and the index of the first one whose content is dropped
+        let mut flattened: Vec<String> = Vec::new();
+        let mut dropped_from: Option<usize> = None;
+
+        while let Some(token) = self.next_token() {
+            let dropping = dropped_from.is_some();
+
+            match token {
+                Token::Start {
+                    name,
+                    attributes,
+                    self_closing,
+                } => {
+                    // Inside of flattened tags, the open paragraph or list item isn't the enclosing tag anymore
+                    let top = stack
+                        .last()
+                        .filter(|_| flattened.is_empty())
+                        .map(|(name, ..)| name.as_str());
+                    if (top == Some("p") && CLOSES_PARAGRAPH.contains(&name.as_str()))
+                        || (top == Some("li") && name == "li")
+                    {
+                        close(&mut stack);
+                    }
+
+                    if RAW_TEXT_TAGS.contains(&name.as_str()) {
+                        let content = self.raw_text(&name);
+                        let node = Node::Tag {
+                            name,
+                            attributes,
+                            children: Children(vec![Node::Text(Cow::Borrowed(content))]),
+                        };
+
+                        if !dropping {
+                            stack.last_mut().expect("root is never closed").2.push(node);
+                        }
+                    } else if self_closing || VOID_TAGS.contains(&name.as_str()) {
+                        let node = Node::Tag {
+                            name,
+                            attributes,
+                            children: Children::default(),
+                        };
+
+                        if !dropping {
+                            stack.last_mut().expect("root is never closed").2.push(node);
+                        }
+                    } else if dropping || stack.len() > MAX_DEPTH {
+                        if !dropping && DROPPED_TAGS.contains(&name.as_str()) {
+                            dropped_from = Some(flattened.len());
+                        }
+                        flattened.push(name);
+                    } else {
+                        stack.push((name, attributes, Vec::new()));
+                    }
+                }
+                Token::End(name) => {
+                    // Only the innermost flattened tags are searched to keep misnested end tags cheap
+                    let flattened_depth = flattened
+                        .iter()
+                        .rev()
+                        .take(MAX_DEPTH)
+                        .position(|open| *open == name)
+                        .map(|idx| flattened.len() - idx - 1);
+
+                    if let Some(depth) = flattened_depth {
+                        flattened.truncate(depth);
+                        dropped_from = dropped_from.filter(|&from| from < depth);
+                    } else if let Some(depth) =
+                        stack.iter().skip(1).rposition(|(open, ..)| *open == name)
+                    {
+                        // Closing an ancestor implicitly closes the flattened tags as well
+                        flattened.clear();
+                        dropped_from = None;
+                        while stack.len() > depth + 1 {
+                            close(&mut stack);
+                        }
+                    }
+
+                    // End tags without a matching start tag are ignored
+                }
+                Token::Text(text) => {
+                    if !dropping {
+                        let text = decode_entities(text);
+                        stack
+                            .last_mut()
+                            .expect("root is never closed")
+                            .2
+                            .push(Node::Text(text));
+                    }
+                }
+            }
+        }
+
+        while stack.len() > 1 {
+            close(&mut stack);
+        }
+
+        stack
+            .pop()
+            .map(|(.., children)| children)
+            .unwrap_or_default()
+    }
+}
+
+#[inline]
+fn formatting<'a>(kind: FormattingKind<'a>, children: Vec<Element<'a>>) -> Element<'a> {
+    Element::Formatting(Formatting { kind, children })
+}
+
+/// Push text, recognising the custom emotes contained in it
+fn push_text<'a>(text: Cow<'a, str>, out: &mut Vec<Element<'a>>) {
+    fn push_borrowed<'a>(text: &'a str, out: &mut Vec<Element<'a>>) {
+        let mut last_end = 0;
+        for (element, span) in tokens(text) {
+            if !matches!(element, Element::Emote(..)) {
+                continue;
+            }
+
+            if last_end < span.start {
+                out.push(Element::Text(Text {
+                    content: Cow::Borrowed(&text[last_end..span.start]),
+                }));
+            }
+
+            out.push(element);
+            last_end = span.end;
+        }
+
+        if last_end < text.len() {
+            out.push(Element::Text(Text {
+                content: Cow::Borrowed(&text[last_end..]),
+            }));
+        }
+    }
+
+    match text {
+        Cow::Borrowed(text) => push_borrowed(text, out),
+        Cow::Owned(text) => {
+            let mut elements = Vec::new();
+            push_borrowed(&text, &mut elements);
+            for element in elements {
+                out.push(element.into_owned());
+            }
+        }
+    }
+}
+
+fn convert<'a>(nodes: impl IntoIterator<Item = Node<'a>>) -> Vec<Element<'a>> {
+    let mut elements = Vec::new();
+    for node in nodes {
+        convert_node(node, &mut elements);
+    }
+    elements
+}
+
+fn convert_node<'a>(node: Node<'a>, out: &mut Vec<Element<'a>>) {
+    let name = match &node {
+        Node::Tag { name, .. } => name.as_str(),
+        Node::Text(text) => {
+            push_text(text.clone(), out);
+            return;
+        }
+    };
+
+    if DROPPED_TAGS.contains(&name) {
+        return;
+    }
+
+    let kind = match name {
+        "a" => {
+            convert_link(node, out);
+            return;
+        }
+        "b" | "strong" => FormattingKind::Strong,
+        "blockquote" => FormattingKind::BlockQuote,
+        "br" => FormattingKind::LineBreak,
+        "code" => {
+            let Node::Tag { children, .. } = node else {
+                unreachable!();
+            };
+
+            let content = visible_text(&children);
+            out.push(formatting(
+                FormattingKind::Code,
+                vec![Element::Text(Text { content })],
+            ));
+            return;
+        }
+        "del" | "s" | "strike" => FormattingKind::Strikethrough,
+        "em" | "i" => FormattingKind::Emphasis,
+        "img" => {
+            // Some software embeds custom emotes as images with the shortcode as their alternative text
+            if let Some(alt) = node.attribute("alt") {
+                push_text(alt.clone(), out);
+            }
+            return;
+        }
+        "li" => FormattingKind::ListItem,
+        "ol" => FormattingKind::List {
+            start: Some(
+                node.attribute("start")
+                    .and_then(|start| start.trim().parse().ok())
+                    .unwrap_or(1),
+            ),
+        },
+        "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => FormattingKind::Paragraph,
+        "pre" => {
+            convert_code_block(node, out);
+            return;
+        }
+        "small" => FormattingKind::Small,
+        "ul" => FormattingKind::List { start: None },
+        _ => {
+            // Unknown tags, including `<span class="h-card">`, are dropped but their content is kept
+            if let Node::Tag { children, .. } = node {
+                for child in children {
+                    convert_node(child, out);
+                }
+            }
+            return;
+        }
+    };
+
+    let Node::Tag { children, .. } = node else {
+        unreachable!();
+    };
+
+    out.push(formatting(kind, convert(children)));
+}
+
+fn convert_code_block<'a>(node: Node<'a>, out: &mut Vec<Element<'a>>) {
+    let Node::Tag { children, .. } = node else {
+        return;
+    };
+
+    let language = match &*children {
+        [code @ Node::Tag { name, .. }] if name == "code" => code
+            .attribute("class")
+            .and_then(|classes| {
+                classes
+                    .split_ascii_whitespace()
+                    .find_map(|class| class.strip_prefix("language-"))
+                    .map(str::to_owned)
+            })
+            .map(Cow::Owned),
+        _ => None,
+    };
+
+    let content = visible_text(&children);
+    let content = map_cow(content, |content| {
+        content.strip_prefix('\n').unwrap_or(content)
+    });
+    let children = if content.is_empty() {
+        Vec::new()
+    } else {
+        vec![Element::Text(Text { content })]
+    };
+
+    out.push(formatting(FormattingKind::CodeBlock { language }, children));
+}
+
+fn convert_link<'a>(node: Node<'a>, out: &mut Vec<Element<'a>>) {
+    let is_hashtag = node.has_class("hashtag") || node.has_rel("tag");
+    let is_mention = !is_hashtag && (node.has_class("mention") || node.has_class("u-url"));
+    let href = node.attribute("href").cloned();
+
+    let Node::Tag { children, .. } = node else {
+        return;
+    };
+
+    let text = visible_text(&children);
+    let trimmed = map_cow(text.clone(), str::trim);
+
+    if is_hashtag && trimmed.starts_with(['#', '＃']) {
+        let content = map_cow(trimmed.clone(), |text| text.trim_start_matches(['#', '＃']));
+
+        if !content.is_empty() {
+            out.push(Element::Hashtag(Hashtag::new(content)));
+            return;
+        }
+    }
+
+    if is_mention && trimmed.starts_with('@') {
+        let acct = map_cow(trimmed, |text| text.trim_start_matches('@'));
+        let (username, domain) = split_once(acct, '@');
+
+        // Local mentions only display the username, the domain is part of the profile URL
+        let domain = domain.or_else(|| {
+            href.as_deref()
+                .and_then(host)
+                .map(|host| Cow::Owned(host.to_owned()))
+        });
+
+        if !username.is_empty() {
+            out.push(Element::Mention(Mention { username, domain }));
+            return;
+        }
+    }
+
+    let Some(href) = href else {
+        // Anchors without a target are just text
+        for child in children {
+            convert_node(child, out);
+        }
+        return;
+    };
+
+    // Mastodon splits the displayed URL into multiple (partially invisible) spans
+    if *text == *href {
+        out.push(Element::Link(Link { content: href }));
+        return;
+    }
+
+    out.push(formatting(FormattingKind::Link { href }, convert(children)));
+}
@@ -1,11 +1,40 @@
 //!
 //! Parser and transformer intended for usage in the Kitsune social media server
 //!
-//! **Important**: None of the texts are protected against XSS attacks. Keep that in mind.
+//! Plain text posts are handled by [`transform`], Markdown posts by [`markdown::transform`] and MFM posts by [`mfm::transform`].
+//! HTML received from remote servers is handled by [`html::transform_safe`].
+//!
+//! **Important**: [`transform`] and [`Render::render`] don't protect the texts against XSS attacks.
+//! Use [`transform_safe`] and [`Render::render_safe`] if the output ends up being interpreted as HTML.
 //!
 
+use futures_util::{StreamExt, TryStreamExt, stream};
 use logos::{Lexer, Logos, Span};
-use std::{borrow::Cow, error::Error, fmt};
+use std::{
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Write},
+    mem,
+    num::NonZeroUsize,
+};
+
+pub use self::{
+    count::{CountingPolicy, count_characters},
+    extract::{Extracted, Occurrence, extract},
+    hashtag::normalise_hashtag,
+    link::LinkSchemes,
+    sanitize::{Allowlist, DEFAULT_ALLOWLIST, Escape},
+};
+
+mod count;
+mod emote;
+mod extract;
+mod hashtag;
+pub mod html;
+mod link;
+pub mod markdown;
+pub mod mfm;
+mod sanitize;
 
 /// Boxed error
 pub type BoxError = Box<dyn Error + Send + Sync>;
@@ -68,26 +97,30 @@ fn mention_split<'a>(lexer: &Lexer<'a, PostElement<'a>>) -> Option<(&'a str, Opt
     Some(mention_data)
 }
 
+/// Tokens of a post
+///
+/// Links are only recognised with the schemes passed in as the extras of the lexer, see [`LinkSchemes`]
 #[derive(Debug, Logos, PartialEq)]
+#[logos(extras = LinkSchemes)]
 pub enum PostElement<'a> {
     #[regex(r":[\w\d_-]+(@[\w\-_]+\.[\.\w]+)?:", emoji_split)]
     Emote((&'a str, Option<&'a str>)),
 
-    #[regex(
-        r"#[\w_-]+",
-        |lexer| enforce_prefix(lexer).then(|| lexer.slice().trim_start_matches('#')),
-    )]
+    #[regex(r"[#＃]", hashtag::lex)]
     Hashtag(&'a str),
 
     #[regex(r"@[\w\-_]+(@[\w\-_]+\.[\.\w]+)?", mention_split)]
     Mention((&'a str, Option<&'a str>)),
 
-    #[regex(r"[\w]+://[^\s<]+")]
+    #[regex(r"[a-zA-Z][a-zA-Z0-9+\-]*://", link::lex)]
+    #[regex(r"[wW]{3}\.", link::lex)]
     Link(&'a str),
 }
 
 /// Transform a post
 ///
+/// The transformer is called for one element after another, starting with the last one
+///
 /// # Errors
 ///
 /// - Transformation of an element fails
@@ -96,35 +129,235 @@ where
     F: Fn(Element<'a>) -> Fut,
     Fut: Future<Output = Result<Element<'a>>>,
 {
-    let element_iter = {
-        let pairs = Lexer::new(text)
-            .spanned()
-            .flat_map(|(token, span)| token.map(|token| (token, span)));
-
-        Element::from_pairs(pairs)
-            .collect::<Vec<(Element<'a>, Span)>>()
-            .into_iter()
-            .rev()
+    transform_inner(text, transformer, NonZeroUsize::MIN, None).await
+}
+
+/// Transform a post and render it into sanitised HTML
+///
+/// The text surrounding the elements and the text inside of them is HTML-escaped.
+/// Tags and attributes of [`Html`] elements are checked against the allowlist, see [`Render::render_safe`].
+/// Links are only recognised with the URL schemes of the allowlist.
+///
+/// The output is suitable for direct storage as the content of a post.
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_safe<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    allowlist: &Allowlist<'_>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    transform_inner(text, transformer, NonZeroUsize::MIN, Some(allowlist)).await
+}
+
+/// Transform a post, running up to `limit` transformations concurrently
+///
+/// Useful when transformations involve network or database round trips, such as resolving mentions via `WebFinger`.
+/// With a limit above one, the transformations are started in the order the elements appear in, unlike with [`transform`].
+/// The transformed elements are still spliced into the output in the order they appear in.
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_concurrent<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    limit: NonZeroUsize,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    transform_inner(text, transformer, limit, None).await
+}
+
+/// Transform a post and render it into sanitised HTML, running up to `limit` transformations concurrently
+///
+/// See [`transform_safe`] and [`transform_concurrent`]
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_concurrent_safe<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    limit: NonZeroUsize,
+    allowlist: &Allowlist<'_>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    transform_inner(text, transformer, limit, Some(allowlist)).await
+}
+
+async fn transform_inner<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    limit: NonZeroUsize,
+    allowlist: Option<&Allowlist<'_>>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let render = |element: &Element<'_>, out: &mut String| match allowlist {
+        Some(allowlist) => element.render_safe(out, allowlist),
+        None => element.render(out),
+    };
+
+    let schemes = allowlist.map_or(LinkSchemes::DEFAULT, |allowlist| {
+        LinkSchemes::new(allowlist.url_schemes.iter().map(ToString::to_string))
+    });
+
+    let tokens = tokens_with(text, schemes);
+    let elements: Vec<(Element<'a>, Span)> = if limit == NonZeroUsize::MIN {
+        // Sequential transformations run from the last element to the first, as they always have
+        let mut elements = Vec::new();
+        for (element, span) in tokens.collect::<Vec<_>>().into_iter().rev() {
+            elements.push((transformer(element).await?, span));
+        }
+
+        elements.reverse();
+        elements
+    } else {
+        // `buffered` yields the results in the order of the elements
+        stream::iter(tokens)
+            .map(|(element, span)| {
+                let transformation = transformer(element);
+                async move { Ok::<_, BoxError>((transformation.await?, span)) }
+            })
+            .buffered(limit.get())
+            .try_collect()
+            .await?
     };
 
-    let mut out = text.to_string();
-    let mut buffer = String::new();
+    let mut out = String::with_capacity(text.len());
+    let mut last_end = 0;
 
-    for (element, span) in element_iter {
-        let element = transformer(element).await?;
+    for (element, span) in elements {
+        let preceding = Element::Text(Text {
+            content: Cow::Borrowed(&text[last_end..span.start]),
+        });
+        render(&preceding, &mut out);
+        render(&element, &mut out);
 
-        element.render(&mut buffer);
-        out.replace_range(span, &buffer);
-        buffer.clear();
+        last_end = span.end;
     }
 
+    let trailing = Element::Text(Text {
+        content: Cow::Borrowed(&text[last_end..]),
+    });
+    render(&trailing, &mut out);
+
     Ok(out)
 }
 
+/// Lex the emotes, hashtags, links and mentions out of a text
+fn tokens(text: &str) -> impl Iterator<Item = (Element<'_>, Span)> {
+    tokens_with(text, LinkSchemes::DEFAULT)
+}
+
+/// Lex the emotes, hashtags, links and mentions out of a text, recognising links with the passed schemes
+fn tokens_with(text: &str, schemes: LinkSchemes) -> impl Iterator<Item = (Element<'_>, Span)> {
+    let pairs = Lexer::with_extras(text, schemes)
+        .spanned()
+        .flat_map(|(token, span)| token.map(|token| (token, span)));
+
+    Element::from_pairs(pairs)
+}
+
+/// Transform the emotes, hashtags, links and mentions contained in a tree of elements
+///
+/// Used by front-ends producing element trees, such as [`markdown`]
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_elements<'a, F, Fut>(
+    elements: &mut [Element<'a>],
+    transformer: F,
+) -> Result<()>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    fn collect_tokens<'b, 'a>(
+        elements: &'b mut [Element<'a>],
+        tokens: &mut Vec<&'b mut Element<'a>>,
+    ) {
+        for element in elements {
+            match element {
+                Element::Formatting(formatting) => collect_tokens(&mut formatting.children, tokens),
+                Element::MfmFunction(function) => collect_tokens(&mut function.children, tokens),
+                Element::Html(..) | Element::Text(..) => {}
+                _ => tokens.push(element),
+            }
+        }
+    }
+
+    let mut tokens = Vec::new();
+    collect_tokens(elements, &mut tokens);
+
+    for token in tokens {
+        let element = mem::replace(
+            token,
+            Element::Text(Text {
+                content: Cow::Borrowed(""),
+            }),
+        );
+        *token = transformer(element).await?;
+    }
+
+    Ok(())
+}
+
+/// Write an opening tag
+///
+/// If an allowlist is passed, the tag and its attributes are checked against it.
+/// Attribute values are always escaped. Returns whether the tag was written.
+fn open_tag<'b>(
+    out: &mut impl fmt::Write,
+    tag: &str,
+    attributes: impl Iterator<Item = (&'b str, &'b str)>,
+    allowlist: Option<&Allowlist<'_>>,
+) -> bool {
+    if allowlist.is_some_and(|allowlist| !allowlist.allows_tag(tag)) {
+        return false;
+    }
+
+    let _ = write!(out, "<{tag}");
+    for (name, value) in attributes {
+        if allowlist.is_some_and(|allowlist| !allowlist.allows_attribute(tag, name, value)) {
+            continue;
+        }
+
+        let _ = write!(out, " {name}=\"");
+        let _ = fmt::Write::write_str(&mut Escape(&mut *out), value);
+        let _ = out.write_char('"');
+    }
+    let _ = out.write_char('>');
+
+    true
+}
+
 /// Render something into a string
 pub trait Render {
     /// Render the element into its string representation
     fn render(&self, out: &mut impl fmt::Write);
+
+    /// Render the element into HTML that is safe to embed into a page
+    ///
+    /// Text is HTML-escaped. Tags and attributes not covered by the allowlist are dropped,
+    /// the contents of dropped tags are still rendered.
+    fn render_safe(&self, out: &mut impl fmt::Write, _allowlist: &Allowlist<'_>) {
+        self.render(&mut Escape(out));
+    }
 }
 
 /// Elements of a post
@@ -133,6 +366,9 @@ pub enum Element<'a> {
     /// Emote
     Emote(Emote<'a>),
 
+    /// Formatting, such as emphasis or lists
+    Formatting(Formatting<'a>),
+
     /// Hashtag
     Hashtag(Hashtag<'a>),
 
@@ -142,6 +378,9 @@ pub enum Element<'a> {
     /// Link
     Link(Link<'a>),
 
+    /// Misskey Flavoured Markup function
+    MfmFunction(MfmFunction<'a>),
+
     /// Mention
     Mention(Mention<'a>),
 
@@ -156,13 +395,12 @@ impl<'a> Element<'a> {
     ) -> impl Iterator<Item = (Element<'a>, Span)> {
         pairs.map(|(item, span)| {
             let element = match item {
-                PostElement::Emote((name, domain)) => Self::Emote(Emote {
+                PostElement::Emote((name, Some(domain))) => Self::Emote(Emote {
                     shortcode: Cow::Borrowed(name),
-                    domain: domain.map(Cow::Borrowed),
-                }),
-                PostElement::Hashtag(content) => Self::Hashtag(Hashtag {
-                    content: Cow::Borrowed(content),
+                    domain: Some(Cow::Borrowed(domain)),
                 }),
+                PostElement::Emote((name, None)) => Self::Emote(Emote::from_shortcode(name)),
+                PostElement::Hashtag(content) => Self::Hashtag(Hashtag::new(content)),
                 PostElement::Mention((username, domain)) => Self::Mention(Mention {
                     username: Cow::Borrowed(username),
                     domain: domain.map(Cow::Borrowed),
@@ -175,19 +413,49 @@ impl<'a> Element<'a> {
             (element, span)
         })
     }
+
+    /// Convert the element into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Element<'static> {
+        match self {
+            Self::Emote(emote) => Element::Emote(emote.into_owned()),
+            Self::Formatting(formatting) => Element::Formatting(formatting.into_owned()),
+            Self::Hashtag(hashtag) => Element::Hashtag(hashtag.into_owned()),
+            Self::Html(html) => Element::Html(html.into_owned()),
+            Self::Link(link) => Element::Link(link.into_owned()),
+            Self::MfmFunction(function) => Element::MfmFunction(function.into_owned()),
+            Self::Mention(mention) => Element::Mention(mention.into_owned()),
+            Self::Text(text) => Element::Text(text.into_owned()),
+        }
+    }
 }
 
 impl Render for Element<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
         match self {
             Self::Emote(emote) => emote.render(out),
+            Self::Formatting(formatting) => formatting.render(out),
             Self::Hashtag(hashtag) => hashtag.render(out),
             Self::Html(html) => html.render(out),
             Self::Link(link) => link.render(out),
+            Self::MfmFunction(function) => function.render(out),
             Self::Mention(mention) => mention.render(out),
             Self::Text(text) => text.render(out),
         }
     }
+
+    fn render_safe(&self, out: &mut impl fmt::Write, allowlist: &Allowlist<'_>) {
+        match self {
+            Self::Emote(emote) => emote.render_safe(out, allowlist),
+            Self::Formatting(formatting) => formatting.render_safe(out, allowlist),
+            Self::Hashtag(hashtag) => hashtag.render_safe(out, allowlist),
+            Self::Html(html) => html.render_safe(out, allowlist),
+            Self::Link(link) => link.render_safe(out, allowlist),
+            Self::MfmFunction(function) => function.render_safe(out, allowlist),
+            Self::Mention(mention) => mention.render_safe(out, allowlist),
+            Self::Text(text) => text.render_safe(out, allowlist),
+        }
+    }
 }
 
 /// Emote data
@@ -199,26 +467,297 @@ pub struct Emote<'a> {
     pub domain: Option<Cow<'a, str>>,
 }
 
+impl<'a> Emote<'a> {
+    /// Construct an emote from a shortcode, as rendered by [`Render::render`]
+    ///
+    /// Shortcodes containing an encoded domain, such as `blobcat__R1_example_com`, are mapped back to the remote emote.
+    /// Other shortcodes, including the ones rendered by earlier versions (such as `blobcat__example_com`), are treated as local ones.
+    #[must_use]
+    pub fn from_shortcode(shortcode: &'a str) -> Self {
+        match emote::split_shortcode(shortcode) {
+            Some((shortcode, domain)) => Self {
+                shortcode: Cow::Borrowed(shortcode),
+                domain: Some(Cow::Owned(domain)),
+            },
+            None => Self {
+                shortcode: Cow::Borrowed(shortcode),
+                domain: None,
+            },
+        }
+    }
+
+    /// Check whether a shortcode is reserved for remote emotes
+    ///
+    /// Local emotes mustn't use reserved shortcodes, otherwise [`Emote::from_shortcode`] would read them back as remote ones.
+    #[must_use]
+    pub fn is_reserved_shortcode(shortcode: &str) -> bool {
+        emote::is_reserved(shortcode)
+    }
+}
+
+impl Emote<'_> {
+    /// Convert the emote into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Emote<'static> {
+        Emote {
+            shortcode: Cow::Owned(self.shortcode.into_owned()),
+            domain: self.domain.map(|domain| Cow::Owned(domain.into_owned())),
+        }
+    }
+}
+
 impl Render for Emote<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
-        let _ = match &self.domain {
-            // Needed since other software (like Mastodon or Misskey) don't support characters such as '.', '-' or '@'
-            Some(domain) => write!(
-                out,
-                ":{}__{}:",
-                self.shortcode,
-                domain.replace(['.', '-'], "_")
-            ),
-            None => write!(out, ":{}:", self.shortcode),
+        // Remote domains are encoded since other software (like Mastodon or Misskey) don't support characters such as '.', '-' or '@'
+        let shortcode = emote::render_shortcode(&self.shortcode, self.domain.as_deref());
+        let _ = write!(out, ":{shortcode}:");
+    }
+}
+
+/// Kind of formatting
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum FormattingKind<'a> {
+    /// Block quote
+    BlockQuote,
+
+    /// Centered content
+    Center,
+
+    /// Inline code
+    Code,
+
+    /// Block of code
+    CodeBlock {
+        /// Language the code is written in
+        language: Option<Cow<'a, str>>,
+    },
+
+    /// Emphasis
+    Emphasis,
+
+    /// Line break
+    LineBreak,
+
+    /// Link with a label
+    ///
+    /// The label is represented by the children
+    Link {
+        /// Link target
+        href: Cow<'a, str>,
+    },
+
+    /// List
+    List {
+        /// Number of the first item if the list is ordered
+        start: Option<u64>,
+    },
+
+    /// Item of a list
+    ListItem,
+
+    /// Mathematical formula
+    ///
+    /// The formula is represented by the children
+    Math {
+        /// Whether the formula is displayed as a block
+        block: bool,
+    },
+
+    /// Paragraph
+    Paragraph,
+
+    /// Search box
+    Search {
+        /// Search query
+        query: Cow<'a, str>,
+    },
+
+    /// Small text
+    Small,
+
+    /// Strikethrough
+    Strikethrough,
+
+    /// Strong emphasis
+    Strong,
+}
+
+impl FormattingKind<'_> {
+    /// Convert the formatting kind into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> FormattingKind<'static> {
+        match self {
+            Self::BlockQuote => FormattingKind::BlockQuote,
+            Self::Center => FormattingKind::Center,
+            Self::Code => FormattingKind::Code,
+            Self::CodeBlock { language } => FormattingKind::CodeBlock {
+                language: language.map(|language| Cow::Owned(language.into_owned())),
+            },
+            Self::Emphasis => FormattingKind::Emphasis,
+            Self::LineBreak => FormattingKind::LineBreak,
+            Self::Link { href } => FormattingKind::Link {
+                href: Cow::Owned(href.into_owned()),
+            },
+            Self::List { start } => FormattingKind::List { start },
+            Self::ListItem => FormattingKind::ListItem,
+            Self::Math { block } => FormattingKind::Math { block },
+            Self::Paragraph => FormattingKind::Paragraph,
+            Self::Search { query } => FormattingKind::Search {
+                query: Cow::Owned(query.into_owned()),
+            },
+            Self::Small => FormattingKind::Small,
+            Self::Strikethrough => FormattingKind::Strikethrough,
+            Self::Strong => FormattingKind::Strong,
+        }
+    }
+}
+
+/// Formatting applied to a list of elements
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Formatting<'a> {
+    /// Kind of formatting
+    pub kind: FormattingKind<'a>,
+
+    /// Formatted elements
+    pub children: Vec<Element<'a>>,
+}
+
+impl Formatting<'_> {
+    /// Convert the formatting into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Formatting<'static> {
+        Formatting {
+            kind: self.kind.into_owned(),
+            children: self.children.into_iter().map(Element::into_owned).collect(),
+        }
+    }
+
+    fn render_tags(&self, out: &mut impl fmt::Write, allowlist: Option<&Allowlist<'_>>) {
+        let class;
+        let search;
+        let start;
+        let (tags, attribute): (&[&str], Option<(&str, &str)>) = match &self.kind {
+            FormattingKind::BlockQuote => (&["blockquote"], None),
+            FormattingKind::Center => (&["div"], None),
+            FormattingKind::Code | FormattingKind::Math { block: false } => (&["code"], None),
+            FormattingKind::CodeBlock { language } => {
+                class = language
+                    .as_ref()
+                    .map(|language| format!("language-{language}"));
+                (
+                    &["pre", "code"],
+                    class.as_deref().map(|class| ("class", class)),
+                )
+            }
+            FormattingKind::Emphasis => (&["em"], None),
+            FormattingKind::LineBreak => {
+                open_tag(out, "br", [].into_iter(), allowlist);
+                return;
+            }
+            FormattingKind::Link { href } => (&["a"], Some(("href", href.as_ref()))),
+            FormattingKind::List { start: None } => (&["ul"], None),
+            FormattingKind::List { start: Some(value) } => {
+                start = value.to_string();
+                (&["ol"], (*value != 1).then_some(("start", start.as_str())))
+            }
+            FormattingKind::ListItem => (&["li"], None),
+            FormattingKind::Math { block: true } => (&["pre", "code"], None),
+            FormattingKind::Paragraph => (&["p"], None),
+            FormattingKind::Search { query } => {
+                search = format!("https://www.google.com/search?q={}", percent_encode(query));
+                (&["a"], Some(("href", search.as_str())))
+            }
+            FormattingKind::Small => (&["small"], None),
+            FormattingKind::Strikethrough => (&["del"], None),
+            FormattingKind::Strong => (&["strong"], None),
         };
+
+        // The attribute belongs to the innermost tag
+        let written: Vec<bool> = tags
+            .iter()
+            .enumerate()
+            .map(|(idx, tag)| {
+                let attributes = attribute.filter(|_| idx == tags.len() - 1);
+                open_tag(out, tag, attributes.into_iter(), allowlist)
+            })
+            .collect();
+
+        for child in &self.children {
+            match allowlist {
+                Some(allowlist) => child.render_safe(out, allowlist),
+                None => child.render(out),
+            }
+        }
+
+        for (tag, written) in tags.iter().zip(written).rev() {
+            if written {
+                let _ = write!(out, "</{tag}>");
+            }
+        }
     }
 }
 
+impl Render for Formatting<'_> {
+    fn render(&self, out: &mut impl fmt::Write) {
+        self.render_tags(out, None);
+    }
+
+    fn render_safe(&self, out: &mut impl fmt::Write, allowlist: &Allowlist<'_>) {
+        self.render_tags(out, Some(allowlist));
+    }
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+
+    encoded
+}
+
 /// Hashtag
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Hashtag<'a> {
-    /// Hashtag name
+    /// Hashtag name, as written in the post
     pub content: Cow<'a, str>,
+
+    /// Normalised name, used to look up the hashtag
+    pub key: Cow<'a, str>,
+}
+
+impl<'a> Hashtag<'a> {
+    /// Construct a hashtag from its name
+    ///
+    /// The key is derived via [`normalise_hashtag`].
+    /// Names that aren't valid hashtags, such as the ones of remote servers with different rules, are lowercased instead.
+    #[must_use]
+    pub fn new(content: impl Into<Cow<'a, str>>) -> Self {
+        let content = content.into();
+        let key = normalise_hashtag(&content).unwrap_or_else(|| content.to_lowercase());
+        let key = match content {
+            Cow::Borrowed(content) if content == key => Cow::Borrowed(content),
+            _ => Cow::Owned(key),
+        };
+
+        Self { content, key }
+    }
+}
+
+impl Hashtag<'_> {
+    /// Convert the hashtag into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Hashtag<'static> {
+        Hashtag {
+            content: Cow::Owned(self.content.into_owned()),
+            key: Cow::Owned(self.key.into_owned()),
+        }
+    }
 }
 
 impl Render for Hashtag<'_> {
@@ -240,18 +779,51 @@ pub struct Html<'a> {
     pub content: Box<Element<'a>>,
 }
 
+impl Html<'_> {
+    /// Convert the HTML element into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Html<'static> {
+        Html {
+            tag: Cow::Owned(self.tag.into_owned()),
+            attributes: self
+                .attributes
+                .into_iter()
+                .map(|(name, value)| {
+                    (
+                        Cow::Owned(name.into_owned()),
+                        Cow::Owned(value.into_owned()),
+                    )
+                })
+                .collect(),
+            content: Box::new(self.content.into_owned()),
+        }
+    }
+
+    fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value.as_ref()))
+    }
+}
+
 impl Render for Html<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
-        let _ = write!(out, "<{}", self.tag);
-        for (name, value) in &self.attributes {
-            let _ = write!(out, " {name}=\"{value}\"");
-        }
-        let _ = out.write_char('>');
+        open_tag(out, &self.tag, self.attributes(), None);
 
         self.content.render(out);
 
         let _ = write!(out, "</{}>", self.tag);
     }
+
+    fn render_safe(&self, out: &mut impl fmt::Write, allowlist: &Allowlist<'_>) {
+        let written = open_tag(out, &self.tag, self.attributes(), Some(allowlist));
+
+        self.content.render_safe(out, allowlist);
+
+        if written {
+            let _ = write!(out, "</{}>", self.tag);
+        }
+    }
 }
 
 /// Link
@@ -261,12 +833,116 @@ pub struct Link<'a> {
     pub content: Cow<'a, str>,
 }
 
+impl Link<'_> {
+    /// Convert the link into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Link<'static> {
+        Link {
+            content: Cow::Owned(self.content.into_owned()),
+        }
+    }
+
+    /// Target of the link, suitable for an `href` attribute
+    ///
+    /// Internationalised hostnames are converted into punycode and links without a scheme point to HTTPS
+    #[must_use]
+    pub fn href(&self) -> Cow<'_, str> {
+        let (before, host, after) = link::split_host(&self.content);
+        let scheme = if link::is_schemeless(&self.content) {
+            "https://"
+        } else {
+            ""
+        };
+
+        match link::host_to_ascii(host) {
+            Cow::Borrowed(..) if scheme.is_empty() => Cow::Borrowed(&self.content),
+            host => Cow::Owned(format!("{scheme}{before}{host}{after}")),
+        }
+    }
+
+    /// Human-readable form of the link
+    ///
+    /// Hostnames in punycode are converted into Unicode, unless they could be mistaken for a different hostname.
+    /// Rendering the link keeps it as it was written, use this to display it differently.
+    #[must_use]
+    pub fn display(&self) -> Cow<'_, str> {
+        let (before, host, after) = link::split_host(&self.content);
+
+        match link::host_to_unicode(host) {
+            Cow::Borrowed(..) => Cow::Borrowed(&self.content),
+            Cow::Owned(host) => Cow::Owned(format!("{before}{host}{after}")),
+        }
+    }
+}
+
 impl Render for Link<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
         let _ = out.write_str(&self.content);
     }
 }
 
+/// Misskey Flavoured Markup function, such as `$[spin.speed=2s content]`
+///
+/// Rendered into HTML the same way Misskey downgrades its functions for clients that don't understand MFM,
+/// by wrapping the content into an `<i>` tag
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct MfmFunction<'a> {
+    /// Name of the function
+    pub name: Cow<'a, str>,
+
+    /// Arguments, with an optional value each
+    pub args: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>,
+
+    /// Content the function is applied to
+    pub children: Vec<Element<'a>>,
+}
+
+impl MfmFunction<'_> {
+    /// Convert the function into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> MfmFunction<'static> {
+        MfmFunction {
+            name: Cow::Owned(self.name.into_owned()),
+            args: self
+                .args
+                .into_iter()
+                .map(|(name, value)| {
+                    (
+                        Cow::Owned(name.into_owned()),
+                        value.map(|value| Cow::Owned(value.into_owned())),
+                    )
+                })
+                .collect(),
+            children: self.children.into_iter().map(Element::into_owned).collect(),
+        }
+    }
+
+    fn render_tags(&self, out: &mut impl fmt::Write, allowlist: Option<&Allowlist<'_>>) {
+        let written = open_tag(out, "i", [].into_iter(), allowlist);
+
+        for child in &self.children {
+            match allowlist {
+                Some(allowlist) => child.render_safe(out, allowlist),
+                None => child.render(out),
+            }
+        }
+
+        if written {
+            let _ = out.write_str("</i>");
+        }
+    }
+}
+
+impl Render for MfmFunction<'_> {
+    fn render(&self, out: &mut impl fmt::Write) {
+        self.render_tags(out, None);
+    }
+
+    fn render_safe(&self, out: &mut impl fmt::Write, allowlist: &Allowlist<'_>) {
+        self.render_tags(out, Some(allowlist));
+    }
+}
+
 /// Mention
 #[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Mention<'a> {
@@ -277,6 +953,17 @@ pub struct Mention<'a> {
     pub domain: Option<Cow<'a, str>>,
 }
 
+impl Mention<'_> {
+    /// Convert the mention into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Mention<'static> {
+        Mention {
+            username: Cow::Owned(self.username.into_owned()),
+            domain: self.domain.map(|domain| Cow::Owned(domain.into_owned())),
+        }
+    }
+}
+
 impl Render for Mention<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
         let _ = write!(out, "@{}", self.username);
@@ -294,6 +981,16 @@ pub struct Text<'a> {
     pub content: Cow<'a, str>,
 }
 
+impl Text<'_> {
+    /// Convert the text into a version that doesn't borrow from the source text
+    #[must_use]
+    pub fn into_owned(self) -> Text<'static> {
+        Text {
+            content: Cow::Owned(self.content.into_owned()),
+        }
+    }
+}
+
 impl Render for Text<'_> {
     fn render(&self, out: &mut impl fmt::Write) {
         let _ = out.write_str(&self.content);
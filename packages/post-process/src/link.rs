@@ -0,0 +1,236 @@
+//!
+//! Recognition of links in plain text and conversion of their hostnames
+//!
+
+use crate::{PostElement, enforce_prefix};
+use idna::uts46::{AsciiDenyList, DnsLength, Hyphens, Uts46};
+use logos::Lexer;
+use std::borrow::Cow;
+use unicode_script::{Script, UnicodeScript};
+
+/// Characters that end a sentence rather than the link they follow
+const TRAILING_PUNCTUATION: &[char] = &[
+    '.', ',', ':', ';', '!', '?', '\'', '"', '…', '。', '、', '！', '？',
+];
+
+/// Schemes links are recognised with, compared case-insensitively
+///
+/// Links without a scheme, such as `www.example.com`, are always recognised and point to HTTPS
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkSchemes(Cow<'static, [Cow<'static, str>]>);
+
+impl LinkSchemes {
+    /// Schemes recognised by default
+    pub const DEFAULT: Self = Self(Cow::Borrowed(&[
+        Cow::Borrowed("http"),
+        Cow::Borrowed("https"),
+    ]));
+
+    /// Construct a new set of schemes
+    pub fn new<I, S>(schemes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Cow<'static, str>>,
+    {
+        Self(schemes.into_iter().map(Into::into).collect())
+    }
+
+    /// Check whether links with the scheme are recognised
+    #[must_use]
+    pub fn allows(&self, scheme: &str) -> bool {
+        self.0
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+}
+
+impl Default for LinkSchemes {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Length of the link following its scheme
+///
+/// The link ends at whitespace or at a closing bracket without an opening counterpart inside the link.
+/// Punctuation at its end is assumed to belong to the surrounding sentence.
+fn length(rest: &str) -> usize {
+    let mut open = Vec::new();
+    let mut end = 0;
+
+    for (idx, c) in rest.char_indices() {
+        match c {
+            '(' | '[' | '{' => open.push(c),
+            ')' | ']' | '}' => {
+                let opening = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+
+                if open.pop() != Some(opening) {
+                    break;
+                }
+            }
+            '<' | '>' => break,
+            c if c.is_whitespace() => break,
+            _ => {}
+        }
+
+        end = idx + c.len_utf8();
+    }
+
+    rest[..end].trim_end_matches(TRAILING_PUNCTUATION).len()
+}
+
+/// Lexer callback extending the matched scheme or `www.` prefix to the full link
+pub fn lex<'a>(lexer: &mut Lexer<'a, PostElement<'a>>) -> Option<&'a str> {
+    if !enforce_prefix(lexer) {
+        return None;
+    }
+
+    let scheme = lexer.slice().strip_suffix("://");
+    if scheme.is_some_and(|scheme| !lexer.extras.allows(scheme)) {
+        return None;
+    }
+
+    let rest = lexer.remainder();
+    let length = length(rest);
+    if !rest[..length].starts_with(|c: char| c.is_alphanumeric() || c == '[') {
+        return None;
+    }
+
+    lexer.bump(length);
+    Some(lexer.slice())
+}
+
+/// Check whether the link was written without a scheme
+pub fn is_schemeless(link: &str) -> bool {
+    link.get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("www."))
+}
+
+/// Split a link into the part preceding its host, the host and the part following it
+pub fn split_host(link: &str) -> (&str, &str, &str) {
+    let authority_start = if is_schemeless(link) {
+        0
+    } else {
+        link.find("://").map_or(0, |idx| idx + 3)
+    };
+    let authority_end = link[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(link.len(), |idx| authority_start + idx);
+
+    let authority = &link[authority_start..authority_end];
+    let host_start = authority
+        .rfind('@')
+        .map_or(authority_start, |idx| authority_start + idx + 1);
+
+    let host = &link[host_start..authority_end];
+    let host_end = if host.starts_with('[') {
+        authority_end
+    } else {
+        host.find(':').map_or(authority_end, |idx| host_start + idx)
+    };
+
+    (
+        &link[..host_start],
+        &link[host_start..host_end],
+        &link[host_end..],
+    )
+}
+
+/// Cyrillic letters that look like Latin ones
+///
+/// Labels consisting only of these spell out Latin words, such as `аррӏе`, and are kept in punycode
+const LATIN_LOOKALIKE_CYRILLIC: &[char] = &[
+    'а', 'г', 'е', 'і', 'ј', 'о', 'п', 'р', 'с', 'у', 'х', 'ъ', 'ы', 'ь', 'ю', 'ѕ', 'ѡ', 'ѵ', 'ҽ',
+    'һ', 'ӏ', 'ԁ', 'ԍ', 'ԗ', 'ԛ', 'ԝ',
+];
+
+/// Combinations of scripts that are commonly used together in a single label
+const SCRIPT_COMBINATIONS: &[&[Script]] = &[
+    &[
+        Script::Latin,
+        Script::Han,
+        Script::Hiragana,
+        Script::Katakana,
+    ],
+    &[Script::Latin, Script::Han, Script::Bopomofo],
+    &[Script::Latin, Script::Han, Script::Hangul],
+];
+
+/// Check whether a label can be displayed in Unicode without being mistaken for a different one
+///
+/// The label has to be written in a single script (or a combination commonly used for a single language),
+/// and must not be a Cyrillic label made up of lookalikes of Latin letters, unless the top-level domain is Cyrillic as well.
+fn is_safe_label(label: &[char], tld: &[char]) -> bool {
+    let mut scripts: Vec<Script> = Vec::new();
+    for script in label.iter().map(UnicodeScript::script) {
+        if !matches!(script, Script::Common | Script::Inherited) && !scripts.contains(&script) {
+            scripts.push(script);
+        }
+    }
+
+    let single_script = scripts.len() <= 1
+        || SCRIPT_COMBINATIONS
+            .iter()
+            .any(|combination| scripts.iter().all(|script| combination.contains(script)));
+    if !single_script {
+        return false;
+    }
+
+    let lookalike = scripts == [Script::Cyrillic]
+        && label
+            .iter()
+            .all(|&c| c.script() != Script::Cyrillic || LATIN_LOOKALIKE_CYRILLIC.contains(&c));
+    let cyrillic_tld = tld.iter().any(|c| c.script() == Script::Cyrillic);
+
+    !lookalike || cyrillic_tld
+}
+
+/// Convert a host into its ASCII representation, as specified by UTS #46
+///
+/// Internationalised labels are mapped (which, among other things, lowercases and NFC-normalises them) and converted into punycode.
+/// Ideographic full stops, such as `。`, separate labels just like `.`.
+/// Hosts that can't be converted are kept as they are.
+pub fn host_to_ascii(host: &str) -> Cow<'_, str> {
+    if host.is_ascii() {
+        return Cow::Borrowed(host);
+    }
+
+    Uts46::new()
+        .to_ascii(
+            host.as_bytes(),
+            AsciiDenyList::URL,
+            Hyphens::Allow,
+            DnsLength::Ignore,
+        )
+        .map_or(Cow::Borrowed(host), |ascii| Cow::Owned(ascii.into_owned()))
+}
+
+/// Convert the punycode labels of a host into their Unicode representation, as specified by UTS #46
+///
+/// Labels that fail the checks of [`is_safe_label`] are kept in punycode, hosts that can't be converted are kept as they are
+pub fn host_to_unicode(host: &str) -> Cow<'_, str> {
+    let has_punycode = host.split('.').any(|label| {
+        label
+            .get(..4)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("xn--"))
+    });
+    if !has_punycode {
+        return Cow::Borrowed(host);
+    }
+
+    let (unicode, result) = Uts46::new().to_user_interface(
+        host.as_bytes(),
+        AsciiDenyList::URL,
+        Hyphens::Allow,
+        |label, tld, _bidi| is_safe_label(label, tld),
+    );
+
+    match unicode {
+        Cow::Owned(unicode) if result.is_ok() && unicode != host => Cow::Owned(unicode),
+        _ => Cow::Borrowed(host),
+    }
+}
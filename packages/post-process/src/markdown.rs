@@ -0,0 +1,918 @@
+//!
+//! Markdown front-end
+//!
+//! Supports a subset of `CommonMark`: emphasis, code spans, fenced code blocks, block quotes, lists and links with labels.
+//! Emotes, hashtags, links and mentions are recognised in the text, just like in plain text posts, but not inside of code.
+//!
+//! Line breaks inside of paragraphs are kept, as is customary for posts.
+//!
+
+use crate::{
+    Allowlist, Element, Formatting, FormattingKind, Render, Result, Text, tokens,
+    transform_elements,
+};
+use logos::Span;
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
+
+/// Maximum nesting depth of block quotes and lists, and of emphasis and links
+///
+/// Blocks nested deeper are kept as paragraphs and inline constructs as literal text, like in the MFM front-end
+const MAX_DEPTH: usize = 20;
+
+/// Parse a Markdown post into a tree of elements
+#[must_use]
+pub fn parse(text: &str) -> Vec<Element<'_>> {
+    parse_blocks(text, 0)
+}
+
+/// Transform a Markdown post and render it into HTML
+///
+/// The transformer is called for every emote, hashtag, link and mention outside of code
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform<'a, F, Fut>(text: &'a str, transformer: F) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let mut elements = parse(text);
+    transform_elements(&mut elements, transformer).await?;
+
+    let mut out = String::with_capacity(text.len());
+    for element in &elements {
+        element.render(&mut out);
+    }
+
+    Ok(out)
+}
+
+/// Transform a Markdown post and render it into sanitised HTML
+///
+/// See [`crate::transform_safe`]
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_safe<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    allowlist: &Allowlist<'_>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let mut elements = parse(text);
+    transform_elements(&mut elements, transformer).await?;
+
+    let mut out = String::with_capacity(text.len());
+    for element in &elements {
+        element.render_safe(&mut out, allowlist);
+    }
+
+    Ok(out)
+}
+
+#[inline]
+fn formatting<'a>(kind: FormattingKind<'a>, children: Vec<Element<'a>>) -> Element<'a> {
+    Element::Formatting(Formatting { kind, children })
+}
+
+#[inline]
+fn text(content: &str) -> Element<'_> {
+    Element::Text(Text {
+        content: Cow::Borrowed(content),
+    })
+}
+
+#[inline]
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+#[inline]
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+struct Line<'a> {
+    start: usize,
+    content: &'a str,
+}
+
+fn lines(text: &str) -> Vec<Line<'_>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|raw| {
+            let line = Line {
+                start,
+                content: raw.trim_end_matches(['\n', '\r']),
+            };
+            start += raw.len();
+            line
+        })
+        .collect()
+}
+
+struct Fence<'a> {
+    marker: u8,
+    len: usize,
+    language: Option<&'a str>,
+}
+
+fn fence(line: &str) -> Option<Fence<'_>> {
+    let indent = indentation(line);
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let marker = *rest.as_bytes().first()?;
+    if marker != b'`' && marker != b'~' {
+        return None;
+    }
+
+    let len = rest.bytes().take_while(|&byte| byte == marker).count();
+    let info = rest[len..].trim();
+    if len < 3 || (marker == b'`' && info.contains('`')) {
+        return None;
+    }
+
+    Some(Fence {
+        marker,
+        len,
+        language: info.split_whitespace().next(),
+    })
+}
+
+fn closes_fence(line: &str, fence: &Fence<'_>) -> bool {
+    let indent = indentation(line);
+    if indent > 3 {
+        return false;
+    }
+
+    let rest = &line[indent..];
+    let len = rest
+        .bytes()
+        .take_while(|&byte| byte == fence.marker)
+        .count();
+    len >= fence.len && is_blank(&rest[len..])
+}
+
+fn quote_content(line: &str) -> Option<&str> {
+    let indent = indentation(line);
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = line[indent..].strip_prefix('>')?;
+    Some(rest.strip_prefix(' ').unwrap_or(rest))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ListKind {
+    Bullet(u8),
+    Ordered(u8),
+}
+
+struct ListMarker {
+    kind: ListKind,
+    start: Option<u64>,
+    content_offset: usize,
+    empty: bool,
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indentation(line);
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let first = *rest.as_bytes().first()?;
+    let (kind, start, marker_len) = if matches!(first, b'-' | b'*' | b'+') {
+        (ListKind::Bullet(first), None, 1)
+    } else {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 || digits > 9 {
+            return None;
+        }
+
+        let delimiter = *rest.as_bytes().get(digits)?;
+        if delimiter != b'.' && delimiter != b')' {
+            return None;
+        }
+
+        let start = rest[..digits].parse().ok()?;
+        (ListKind::Ordered(delimiter), Some(start), digits + 1)
+    };
+
+    // Only spaces and tabs separate the marker from the content, other whitespace makes the line a paragraph
+    let after = &rest[marker_len..];
+    let empty = after.bytes().all(|byte| byte == b' ' || byte == b'\t');
+    if !empty && !after.starts_with(' ') {
+        return None;
+    }
+
+    // Content indented by more than four spaces is an indented code block, which we render as text
+    let spaces = match indentation(after) {
+        _ if empty => 1,
+        spaces @ 1..=4 => spaces,
+        _ => 1,
+    };
+
+    Some(ListMarker {
+        kind,
+        start,
+        content_offset: indent + marker_len + spaces,
+        empty,
+    })
+}
+
+fn starts_block(line: &str) -> bool {
+    fence(line).is_some() || quote_content(line).is_some()
+}
+
+fn interrupts_paragraph(line: &str) -> bool {
+    starts_block(line)
+        || list_marker(line)
+            .is_some_and(|marker| !marker.empty && marker.start.is_none_or(|start| start == 1))
+}
+
+fn parse_blocks(text: &str, depth: usize) -> Vec<Element<'_>> {
+    let lines = lines(text);
+    let mut elements = Vec::new();
+    let mut idx = 0;
+
+    while idx < lines.len() {
+        let line = lines[idx].content;
+        if is_blank(line) {
+            idx += 1;
+            continue;
+        }
+
+        let nested = depth < MAX_DEPTH;
+        let (element, next) = if let Some(fence) = fence(line) {
+            code_block(text, &lines, idx, &fence)
+        } else if nested && quote_content(line).is_some() {
+            block_quote(&lines, idx, depth + 1)
+        } else if let Some(marker) = list_marker(line).filter(|_| nested) {
+            list(&lines, idx, &marker, depth + 1)
+        } else {
+            paragraph(text, &lines, idx)
+        };
+
+        elements.push(element);
+        idx = next;
+    }
+
+    elements
+}
+
+fn code_block<'a>(
+    text: &'a str,
+    lines: &[Line<'a>],
+    idx: usize,
+    fence: &Fence<'a>,
+) -> (Element<'a>, usize) {
+    let mut end = idx + 1;
+    while end < lines.len() && !closes_fence(lines[end].content, fence) {
+        end += 1;
+    }
+
+    let content_start = lines.get(idx + 1).map_or(text.len(), |line| line.start);
+    let content_end = lines.get(end).map_or(text.len(), |line| line.start);
+    let content = &text[content_start..content_end.max(content_start)];
+    let content = content.strip_suffix('\n').unwrap_or(content);
+    let content = content.strip_suffix('\r').unwrap_or(content);
+
+    let children = if content.is_empty() {
+        Vec::new()
+    } else {
+        vec![self::text(content)]
+    };
+
+    let element = formatting(
+        FormattingKind::CodeBlock {
+            language: fence.language.map(Cow::Borrowed),
+        },
+        children,
+    );
+
+    (element, (end + 1).min(lines.len()))
+}
+
+fn block_quote(lines: &[Line<'_>], idx: usize, depth: usize) -> (Element<'static>, usize) {
+    let mut content = String::new();
+    let mut end = idx;
+    while let Some(inner) = lines.get(end).and_then(|line| quote_content(line.content)) {
+        content.push_str(inner);
+        content.push('\n');
+        end += 1;
+    }
+
+    let children = parse_blocks(&content, depth)
+        .into_iter()
+        .map(Element::into_owned)
+        .collect();
+
+    (formatting(FormattingKind::BlockQuote, children), end)
+}
+
+fn list(
+    lines: &[Line<'_>],
+    idx: usize,
+    first: &ListMarker,
+    depth: usize,
+) -> (Element<'static>, usize) {
+    let mut items = Vec::new();
+    let mut tight = true;
+    let mut idx = idx;
+
+    while let Some(marker) = lines.get(idx).and_then(|line| list_marker(line.content)) {
+        if marker.kind != first.kind {
+            break;
+        }
+
+        let first_line = lines[idx].content;
+        let mut content = String::from(&first_line[marker.content_offset.min(first_line.len())..]);
+        content.push('\n');
+
+        let mut end = idx + 1;
+        let mut pending_blank = false;
+        while let Some(line) = lines.get(end).map(|line| line.content) {
+            if is_blank(line) {
+                pending_blank = true;
+                content.push('\n');
+            } else if indentation(line) >= marker.content_offset {
+                tight &= !pending_blank;
+                pending_blank = false;
+                content.push_str(&line[marker.content_offset..]);
+                content.push('\n');
+            } else if !pending_blank && !starts_block(line) && list_marker(line).is_none() {
+                // Lazy continuation of a paragraph
+                content.push_str(line.trim_start());
+                content.push('\n');
+            } else {
+                break;
+            }
+
+            end += 1;
+        }
+
+        let children = parse_blocks(&content, depth)
+            .into_iter()
+            .map(Element::into_owned)
+            .collect();
+        items.push(children);
+
+        idx = end;
+        if pending_blank {
+            let continues = lines
+                .get(idx)
+                .and_then(|line| list_marker(line.content))
+                .is_some_and(|next| next.kind == first.kind);
+
+            if !continues {
+                break;
+            }
+
+            tight = false;
+        }
+    }
+
+    let items = items
+        .into_iter()
+        .map(|children: Vec<Element<'static>>| {
+            let children = if tight {
+                // Tight lists don't wrap their items in paragraphs
+                children
+                    .into_iter()
+                    .flat_map(|child| match child {
+                        Element::Formatting(Formatting {
+                            kind: FormattingKind::Paragraph,
+                            children,
+                        }) => children,
+                        child => vec![child],
+                    })
+                    .collect()
+            } else {
+                children
+            };
+
+            formatting(FormattingKind::ListItem, children)
+        })
+        .collect();
+
+    let element = formatting(FormattingKind::List { start: first.start }, items);
+    (element, idx)
+}
+
+fn paragraph<'a>(text: &'a str, lines: &[Line<'a>], idx: usize) -> (Element<'a>, usize) {
+    let mut end = idx + 1;
+    while let Some(line) = lines.get(end).map(|line| line.content) {
+        if is_blank(line) || interrupts_paragraph(line) {
+            break;
+        }
+
+        end += 1;
+    }
+
+    let last = &lines[end - 1];
+    let content = text[lines[idx].start..last.start + last.content.len()].trim();
+    let element = formatting(FormattingKind::Paragraph, parse_inline(content));
+
+    (element, end)
+}
+
+fn parse_inline(text: &str) -> Vec<Element<'_>> {
+    let inline = Inline {
+        text,
+        tokens: tokens(text).collect(),
+        delimiters: RefCell::default(),
+    };
+
+    inline.parse(0..text.len(), false, 0)
+}
+
+struct Inline<'a> {
+    text: &'a str,
+    tokens: Vec<(Element<'a>, Span)>,
+    /// Delimiter runs of the ranges emphasis was searched in, keyed by the delimiter and the range
+    delimiters: RefCell<HashMap<(u8, usize, usize), Rc<Delimiters>>>,
+}
+
+/// Runs of one delimiter in a range and how they nest
+///
+/// Searching the closer of every opening delimiter from scratch is quadratic,
+/// so the runs are collected once and the closers are found by following the levels instead.
+struct Delimiters {
+    runs: Vec<Range<usize>>,
+    /// Number of delimiters opened and not yet closed after every run, relative to the start of the range
+    levels: Vec<isize>,
+    /// Next run after every run that lowers the level below the one after it
+    next_lower: Vec<Option<usize>>,
+    /// Run closing a strong emphasis after every run that only had a single delimiter left over,
+    /// and the level the search continues from
+    after_reset: Vec<Option<(usize, isize)>>,
+}
+
+impl Delimiters {
+    fn new(runs: Vec<(Range<usize>, isize)>) -> Self {
+        let mut levels = Vec::with_capacity(runs.len());
+        let mut level = 0;
+        for (_, delta) in &runs {
+            level += delta;
+            levels.push(level);
+        }
+
+        let mut next_lower = vec![None; runs.len()];
+        let mut pending: Vec<usize> = Vec::new();
+        for (idx, &level) in levels.iter().enumerate() {
+            while let Some(&previous) = pending.last() {
+                if levels[previous] <= level {
+                    break;
+                }
+
+                next_lower[previous] = Some(idx);
+                pending.pop();
+            }
+
+            pending.push(idx);
+        }
+
+        let mut after_reset = vec![None; runs.len()];
+        for idx in (0..runs.len()).rev() {
+            after_reset[idx] = next_lower[idx].and_then(|next| {
+                if levels[idx] - levels[next] >= 2 {
+                    Some((next, levels[idx]))
+                } else {
+                    after_reset[next]
+                }
+            });
+        }
+
+        Self {
+            runs: runs.into_iter().map(|(run, _)| run).collect(),
+            levels,
+            next_lower,
+            after_reset,
+        }
+    }
+
+    /// Index of the run containing `pos`
+    fn run_at(&self, pos: usize) -> Option<usize> {
+        let idx = self
+            .runs
+            .partition_point(|run| run.start <= pos)
+            .checked_sub(1)?;
+        self.runs[idx].contains(&pos).then_some(idx)
+    }
+
+    /// Find the position of the `len` delimiters closing an emphasis
+    /// that starts in the run `idx`, with `open` delimiters of that run left after the opening ones
+    fn closer(&self, idx: usize, open: usize, len: usize) -> Option<usize> {
+        let base = self.levels[idx] - open as isize;
+
+        let mut next = self.next_lower[idx];
+        while let Some(candidate) = next {
+            if self.levels[candidate] < base {
+                break;
+            }
+
+            next = self.next_lower[candidate];
+        }
+
+        let closer = next?;
+        let (closer, base) = if base - self.levels[closer] >= len as isize {
+            (closer, base)
+        } else {
+            // The run closed all open delimiters but one, which can't close a strong emphasis on its own
+            self.after_reset[closer]?
+        };
+
+        // Delimiters at the start of the run close the emphasis nested inside
+        let consumed = self.levels[closer - 1] - base;
+        Some(self.runs[closer].start + consumed as usize)
+    }
+}
+
+impl<'a> Inline<'a> {
+    fn token_at(&self, pos: usize, end: usize) -> Option<&(Element<'a>, Span)> {
+        let idx = self
+            .tokens
+            .binary_search_by_key(&pos, |(_, span)| span.start)
+            .ok()?;
+
+        let token = &self.tokens[idx];
+        (token.1.end <= end).then_some(token)
+    }
+
+    fn run_length(&self, pos: usize, end: usize) -> usize {
+        let bytes = &self.text.as_bytes()[pos..end];
+        bytes.iter().take_while(|&&byte| byte == bytes[0]).count()
+    }
+
+    fn char_before(&self, pos: usize) -> Option<char> {
+        self.text[..pos].chars().next_back()
+    }
+
+    fn char_after(&self, pos: usize) -> Option<char> {
+        self.text[pos..].chars().next()
+    }
+
+    fn push_text(&self, elements: &mut Vec<Element<'a>>, range: Range<usize>) {
+        if !range.is_empty() {
+            elements.push(text(&self.text[range]));
+        }
+    }
+
+    fn parse(&self, range: Range<usize>, in_link: bool, depth: usize) -> Vec<Element<'a>> {
+        let bytes = self.text.as_bytes();
+        let mut elements = Vec::new();
+        let mut text_start = range.start;
+        let mut pos = range.start;
+
+        while pos < range.end {
+            // Links can't be nested, so neither tokens nor links are recognised inside of labels
+            if let Some((token, span)) = self.token_at(pos, range.end).filter(|_| !in_link) {
+                self.push_text(&mut elements, text_start..pos);
+                elements.push(token.clone());
+                pos = span.end;
+                text_start = pos;
+                continue;
+            }
+
+            let parsed = match bytes[pos] {
+                b'\\' if pos + 1 < range.end && bytes[pos + 1].is_ascii_punctuation() => {
+                    // The escaped character starts the next run of text
+                    self.push_text(&mut elements, text_start..pos);
+                    text_start = pos + 1;
+                    pos += 2;
+                    continue;
+                }
+                b'\n' => {
+                    let line = self.text[text_start..pos].trim_end_matches([' ', '\t', '\r']);
+                    let next_line = &self.text[pos + 1..range.end];
+                    let indentation =
+                        next_line.len() - next_line.trim_start_matches([' ', '\t']).len();
+
+                    self.push_text(&mut elements, text_start..text_start + line.len());
+                    elements.push(formatting(FormattingKind::LineBreak, Vec::new()));
+                    pos += 1 + indentation;
+                    text_start = pos;
+                    continue;
+                }
+                b'`' => self.code_span(pos, range.end).map(|(content, end)| {
+                    let children = if content.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![text(&self.text[content])]
+                    };
+
+                    (formatting(FormattingKind::Code, children), end)
+                }),
+                b'*' | b'_' if depth < MAX_DEPTH => {
+                    self.emphasis(pos, range.clone())
+                        .map(|(kind, content, end)| {
+                            (
+                                formatting(kind, self.parse(content, in_link, depth + 1)),
+                                end,
+                            )
+                        })
+                }
+                b'[' if !in_link && depth < MAX_DEPTH => {
+                    self.link(pos, range.end).map(|(label, href, end)| {
+                        let kind = FormattingKind::Link {
+                            href: Cow::Borrowed(href),
+                        };
+
+                        (formatting(kind, self.parse(label, true, depth + 1)), end)
+                    })
+                }
+                _ => None,
+            };
+
+            let Some((element, end)) = parsed else {
+                // Unmatched runs of backticks can't start a code span later on
+                pos += if bytes[pos] == b'`' {
+                    self.run_length(pos, range.end)
+                } else {
+                    1
+                };
+                continue;
+            };
+
+            self.push_text(&mut elements, text_start..pos);
+            elements.push(element);
+            pos = end;
+            text_start = end;
+        }
+
+        self.push_text(&mut elements, text_start..range.end);
+        elements
+    }
+
+    /// Find the content of the code span starting at `pos` and the end of the code span
+    fn code_span(&self, pos: usize, end: usize) -> Option<(Range<usize>, usize)> {
+        let bytes = self.text.as_bytes();
+        let run = self.run_length(pos, end);
+
+        let mut search = pos + run;
+        while search < end {
+            if bytes[search] != b'`' {
+                search += 1;
+                continue;
+            }
+
+            let len = self.run_length(search, end);
+            if len == run {
+                let mut content = pos + run..search;
+                let inner = &self.text[content.clone()];
+                if inner.len() > 2
+                    && inner.starts_with(' ')
+                    && inner.ends_with(' ')
+                    && !is_blank(inner)
+                {
+                    content = content.start + 1..content.end - 1;
+                }
+
+                return Some((content, search + len));
+            }
+
+            search += len;
+        }
+
+        None
+    }
+
+    /// Find the kind of emphasis starting at `pos`, its content and its end
+    fn emphasis(
+        &self,
+        pos: usize,
+        range: Range<usize>,
+    ) -> Option<(FormattingKind<'a>, Range<usize>, usize)> {
+        let delimiter = self.text.as_bytes()[pos];
+        let run = self.run_length(pos, range.end);
+
+        let can_open = self
+            .char_after(pos + run)
+            .is_some_and(|after| !after.is_whitespace())
+            && (delimiter != b'_' || !self.char_before(pos).is_some_and(char::is_alphanumeric));
+
+        if !can_open {
+            return None;
+        }
+
+        [(2, FormattingKind::Strong), (1, FormattingKind::Emphasis)]
+            .into_iter()
+            .filter(|(len, _)| *len <= run)
+            .find_map(|(len, kind)| {
+                let closer = self.find_closer(delimiter, len, pos, range.clone())?;
+                Some((kind, pos + len..closer, closer + len))
+            })
+    }
+
+    /// Find the next run of delimiters outside of tokens and code spans, starting the search at `pos`
+    fn next_run(&self, delimiter: u8, mut pos: usize, end: usize) -> Option<Range<usize>> {
+        let bytes = self.text.as_bytes();
+
+        while pos < end {
+            if let Some((_, span)) = self.token_at(pos, end) {
+                pos = span.end;
+                continue;
+            }
+
+            match bytes[pos] {
+                b'\\' => pos += 2,
+                b'`' => {
+                    pos = self
+                        .code_span(pos, end)
+                        .map_or_else(|| pos + self.run_length(pos, end), |(_, end)| end);
+                }
+                byte if byte == delimiter => return Some(pos..pos + self.run_length(pos, end)),
+                _ => pos += 1,
+            }
+        }
+
+        None
+    }
+
+    /// Check whether a run of delimiters can close and whether it can open emphasis
+    fn flanking(&self, delimiter: u8, run: &Range<usize>) -> (bool, bool) {
+        let is_word = |c: char| delimiter == b'_' && c.is_alphanumeric();
+
+        let can_close = self
+            .char_before(run.start)
+            .is_some_and(|before| !before.is_whitespace())
+            && !self.char_after(run.end).is_some_and(is_word);
+        let can_open = self
+            .char_after(run.end)
+            .is_some_and(|after| !after.is_whitespace())
+            && !self.char_before(run.start).is_some_and(is_word);
+
+        (can_close, can_open)
+    }
+
+    /// Collect the runs of delimiters in a range, or return the ones collected before
+    fn delimiters(&self, delimiter: u8, range: Range<usize>) -> Rc<Delimiters> {
+        let key = (delimiter, range.start, range.end);
+        if let Some(delimiters) = self.delimiters.borrow().get(&key) {
+            return Rc::clone(delimiters);
+        }
+
+        let mut runs = Vec::new();
+        let mut pos = range.start;
+        while let Some(run) = self.next_run(delimiter, pos, range.end) {
+            pos = run.end;
+
+            let delta = match self.flanking(delimiter, &run) {
+                (true, _) => -(run.len() as isize),
+                (false, true) => run.len() as isize,
+                (false, false) => 0,
+            };
+            runs.push((run, delta));
+        }
+
+        let delimiters = Rc::new(Delimiters::new(runs));
+        self.delimiters
+            .borrow_mut()
+            .insert(key, Rc::clone(&delimiters));
+
+        delimiters
+    }
+
+    /// Find the position of the `len` delimiters closing an emphasis opened at `pos`
+    fn find_closer(
+        &self,
+        delimiter: u8,
+        len: usize,
+        pos: usize,
+        range: Range<usize>,
+    ) -> Option<usize> {
+        let end = range.end;
+        let delimiters = self.delimiters(delimiter, range);
+
+        // The runs are collected from the start of the range, which only skips over the same tokens and code spans
+        // as the search from the opening delimiter if the opening delimiter is part of one of them
+        let Some(idx) = delimiters.run_at(pos) else {
+            return self.scan_closer(delimiter, len, pos + len, end);
+        };
+
+        // The rest of the opening run opens nested emphasis
+        let run = &delimiters.runs[idx];
+        let open = if pos + len < run.end
+            && self
+                .char_after(run.end)
+                .is_some_and(|after| !after.is_whitespace())
+        {
+            run.end - (pos + len)
+        } else {
+            0
+        };
+
+        delimiters.closer(idx, open, len)
+    }
+
+    /// Find the position of the `len` delimiters closing an emphasis whose content starts at `from` by scanning the text
+    fn scan_closer(&self, delimiter: u8, len: usize, from: usize, end: usize) -> Option<usize> {
+        // Delimiters of nested emphasis that haven't been closed yet
+        let mut open = 0;
+        let mut pos = from;
+
+        while let Some(run) = self.next_run(delimiter, pos, end) {
+            let (can_close, can_open) = self.flanking(delimiter, &run);
+
+            if can_close && run.start > from {
+                let consumed = run.len().min(open);
+                open -= consumed;
+
+                if run.len() - consumed >= len {
+                    return Some(run.start + consumed);
+                }
+            } else if can_open {
+                open += run.len();
+            }
+
+            pos = run.end;
+        }
+
+        None
+    }
+
+    /// Find the label, target and end of the link starting at `pos`
+    fn link(&self, start: usize, end: usize) -> Option<(Range<usize>, &'a str, usize)> {
+        let bytes = &self.text.as_bytes()[..end];
+
+        let mut depth = 0_usize;
+        let mut label_end = start + 1;
+        loop {
+            match *bytes.get(label_end)? {
+                b'\\' => label_end += 1,
+                b'`' => {
+                    if let Some((_, end)) = self.code_span(label_end, end) {
+                        label_end = end;
+                        continue;
+                    }
+                }
+                b'[' => depth += 1,
+                b']' if depth == 0 => break,
+                b']' => depth -= 1,
+                _ => {}
+            }
+
+            label_end += 1;
+        }
+
+        if bytes.get(label_end + 1) != Some(&b'(') {
+            return None;
+        }
+
+        let skip_whitespace = |mut pos: usize| {
+            while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+                pos += 1;
+            }
+            pos
+        };
+
+        let mut pos = skip_whitespace(label_end + 2);
+        let href = if bytes.get(pos) == Some(&b'<') {
+            let close = pos + self.text[pos..end].find(['>', '\n'])?;
+            if bytes[close] != b'>' {
+                return None;
+            }
+
+            let href = &self.text[pos + 1..close];
+            pos = close + 1;
+            href
+        } else {
+            let start = pos;
+            let mut parentheses = 0_usize;
+            while let Some(&byte) = bytes.get(pos) {
+                match byte {
+                    byte if byte.is_ascii_whitespace() => break,
+                    b'\\' => pos += 1,
+                    b'(' => parentheses += 1,
+                    b')' if parentheses == 0 => break,
+                    b')' => parentheses -= 1,
+                    _ => {}
+                }
+
+                pos += 1;
+            }
+
+            &self.text[start..pos.min(end)]
+        };
+
+        pos = skip_whitespace(pos);
+        if let Some(&quote @ (b'"' | b'\'' | b'(')) = bytes.get(pos) {
+            let closing = if quote == b'(' { b')' } else { quote };
+            let title_end = pos + 1 + bytes[pos + 1..].iter().position(|&byte| byte == closing)?;
+            pos = skip_whitespace(title_end + 1);
+        }
+
+        (bytes.get(pos) == Some(&b')')).then(|| (start + 1..label_end, href, pos + 1))
+    }
+}
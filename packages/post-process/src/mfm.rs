@@ -0,0 +1,634 @@
+//!
+//! Misskey Flavoured Markup front-end
+//!
+//! Supports functions (`$[x2 ...]`), `<plain>`, `<center>`, `<small>`, bold, italic, strikethrough,
+//! code, math, quotes, search blocks and links with labels.
+//! Emotes, hashtags, links and mentions are recognised in the text, but not inside of code, math or `<plain>`.
+//!
+//! Rendering the elements downgrades them into plain HTML, the same way Misskey does for clients that don't understand MFM.
+//! The original text is meant to be federated alongside the HTML as `_misskey_content`.
+//!
+
+use crate::{
+    Allowlist, Element, Formatting, FormattingKind, MfmFunction, Render, Result, Text, tokens,
+    transform_elements,
+};
+use logos::Span;
+use std::{borrow::Cow, cell::RefCell, collections::HashSet};
+
+/// Maximum nesting depth of constructs
+///
+/// Anything nested deeper is kept as text, mirroring the limit of Misskey's parser
+const MAX_DEPTH: usize = 20;
+
+/// Suffixes turning a line into a search block
+const SEARCH_SUFFIXES: &[&str] = &["[search]", "[検索]", "search", "検索"];
+
+/// Parse an MFM post into a tree of elements
+#[must_use]
+pub fn parse(text: &str) -> Vec<Element<'_>> {
+    Parser::new(text).parse(0)
+}
+
+/// Transform an MFM post and render it into HTML
+///
+/// The transformer is called for every emote, hashtag, link and mention outside of code, math and `<plain>`
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform<'a, F, Fut>(text: &'a str, transformer: F) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let mut elements = parse(text);
+    transform_elements(&mut elements, transformer).await?;
+
+    let mut out = String::with_capacity(text.len());
+    for element in &elements {
+        element.render(&mut out);
+    }
+
+    Ok(out)
+}
+
+/// Transform an MFM post and render it into sanitised HTML
+///
+/// See [`crate::transform_safe`]
+///
+/// # Errors
+///
+/// - Transformation of an element fails
+pub async fn transform_safe<'a, F, Fut>(
+    text: &'a str,
+    transformer: F,
+    allowlist: &Allowlist<'_>,
+) -> Result<String>
+where
+    F: Fn(Element<'a>) -> Fut,
+    Fut: Future<Output = Result<Element<'a>>>,
+{
+    let mut elements = parse(text);
+    transform_elements(&mut elements, transformer).await?;
+
+    let mut out = String::with_capacity(text.len());
+    for element in &elements {
+        element.render_safe(&mut out, allowlist);
+    }
+
+    Ok(out)
+}
+
+#[inline]
+fn formatting<'a>(kind: FormattingKind<'a>, children: Vec<Element<'a>>) -> Element<'a> {
+    Element::Formatting(Formatting { kind, children })
+}
+
+#[inline]
+fn text(content: &str) -> Element<'_> {
+    Element::Text(Text {
+        content: Cow::Borrowed(content),
+    })
+}
+
+#[inline]
+fn is_name(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Whether the element is laid out as a block, making the line breaks around it redundant
+fn is_block(element: &Element<'_>) -> bool {
+    matches!(
+        element,
+        Element::Formatting(Formatting {
+            kind: FormattingKind::BlockQuote
+                | FormattingKind::Center
+                | FormattingKind::CodeBlock { .. }
+                | FormattingKind::Math { block: true }
+                | FormattingKind::Search { .. },
+            ..
+        })
+    )
+}
+
+/// Construct parsed at some position, together with the position right after it
+type Parsed<'a> = Option<(Element<'a>, usize)>;
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(Element<'a>, Span)>,
+
+    /// Positions at which no construct could be parsed
+    ///
+    /// Without remembering these, unterminated constructs would make parsing take exponential time
+    failed: RefCell<HashSet<(usize, bool)>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            tokens: tokens(text).collect(),
+            failed: RefCell::default(),
+        }
+    }
+
+    fn parse(&self, depth: usize) -> Vec<Element<'a>> {
+        self.parse_inline(0, None, false, depth)
+            .map(|(elements, _)| elements)
+            .unwrap_or_default()
+    }
+
+    fn token_at(&self, pos: usize) -> Option<&(Element<'a>, Span)> {
+        let idx = self
+            .tokens
+            .binary_search_by_key(&pos, |(_, span)| span.start)
+            .ok()?;
+
+        Some(&self.tokens[idx])
+    }
+
+    fn is_line_start(&self, pos: usize) -> bool {
+        pos == 0 || self.text.as_bytes()[pos - 1] == b'\n'
+    }
+
+    fn line_end(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .find('\n')
+            .map_or(self.text.len(), |idx| pos + idx)
+    }
+
+    /// Position after the line break ending a block, if there is one
+    fn skip_line_break(&self, pos: usize) -> usize {
+        if self.text[pos..].starts_with('\n') {
+            pos + 1
+        } else {
+            pos
+        }
+    }
+
+    fn push_text(&self, elements: &mut Vec<Element<'a>>, start: usize, end: usize) {
+        if start < end {
+            elements.push(text(&self.text[start..end]));
+        }
+    }
+
+    /// Parse the inline content starting at `pos` until the terminator
+    ///
+    /// Returns the elements and the position of the terminator, or `None` if the terminator couldn't be found
+    fn parse_inline(
+        &self,
+        mut pos: usize,
+        terminator: Option<&str>,
+        in_link: bool,
+        depth: usize,
+    ) -> Option<(Vec<Element<'a>>, usize)> {
+        // Bail out early instead of parsing the rest of the text just to not find the terminator
+        if terminator.is_some_and(|terminator| !self.text[pos..].contains(terminator)) {
+            return None;
+        }
+
+        let mut elements = Vec::new();
+        let mut text_start = pos;
+
+        while pos < self.text.len() {
+            let rest = &self.text[pos..];
+            if terminator.is_some_and(|terminator| rest.starts_with(terminator)) {
+                self.push_text(&mut elements, text_start, pos);
+                return Some((elements, pos));
+            }
+
+            // Links can't be nested, so neither tokens nor links are recognised inside of labels
+            if let Some((token, span)) = self.token_at(pos).filter(|_| !in_link) {
+                self.push_text(&mut elements, text_start, pos);
+                elements.push(token.clone());
+                pos = span.end;
+                text_start = pos;
+                continue;
+            }
+
+            if rest.starts_with('\n') {
+                self.push_text(&mut elements, text_start, pos);
+                elements.push(formatting(FormattingKind::LineBreak, Vec::new()));
+                pos += 1;
+                text_start = pos;
+                continue;
+            }
+
+            let construct = (depth < MAX_DEPTH)
+                .then(|| self.construct(pos, in_link, depth + 1))
+                .flatten();
+            if let Some((element, end)) = construct {
+                self.push_text(&mut elements, text_start, pos);
+
+                if is_block(&element) {
+                    let after_line_break = matches!(
+                        elements.last(),
+                        Some(Element::Formatting(Formatting {
+                            kind: FormattingKind::LineBreak,
+                            ..
+                        }))
+                    );
+
+                    if after_line_break {
+                        elements.pop();
+                    }
+                }
+
+                elements.push(element);
+                pos = end;
+                text_start = pos;
+                continue;
+            }
+
+            pos += rest.chars().next().map_or(1, char::len_utf8);
+        }
+
+        if terminator.is_some() {
+            return None;
+        }
+
+        self.push_text(&mut elements, text_start, pos);
+        Some((elements, pos))
+    }
+
+    fn construct(&self, pos: usize, in_link: bool, depth: usize) -> Parsed<'a> {
+        if self.failed.borrow().contains(&(pos, in_link)) {
+            return None;
+        }
+
+        let line_start = self.is_line_start(pos);
+        let rest = &self.text[pos..];
+        let parsed = match rest.as_bytes()[0] {
+            b'$' => self.function(pos, in_link, depth),
+            b'<' => self.tag(pos, in_link, depth),
+            b'*' if rest.starts_with("**") => {
+                self.wrapped(pos, "**", "**", FormattingKind::Strong, in_link, depth)
+            }
+            b'*' | b'_' => self.simple_emphasis(pos),
+            b'~' if rest.starts_with("~~") => self.strikethrough(pos, in_link, depth),
+            b'`' if line_start && rest.starts_with("```") => self.code_block(pos),
+            b'`' => self.inline_code(pos),
+            b'\\' if line_start && rest.starts_with("\\[") => self.math_block(pos),
+            b'\\' if rest.starts_with("\\(") => self.math_inline(pos),
+            b'[' if !in_link => self.link(pos + 1, depth),
+            b'?' if !in_link && rest.starts_with("?[") => self.link(pos + 2, depth),
+            b'>' if line_start => self.quote(pos, depth),
+            _ => None,
+        };
+        let parsed = parsed.or_else(|| line_start.then(|| self.search(pos)).flatten());
+
+        if parsed.is_none() {
+            self.failed.borrow_mut().insert((pos, in_link));
+        }
+
+        parsed
+    }
+
+    /// `$[name.arg1,arg2=value content]`
+    fn function(&self, pos: usize, in_link: bool, depth: usize) -> Parsed<'a> {
+        let rest = self.text[pos..].strip_prefix("$[")?;
+        let name_len = rest.bytes().take_while(|&byte| is_name(byte)).count();
+        if name_len == 0 {
+            return None;
+        }
+
+        let name = &rest[..name_len];
+        let mut args = Vec::new();
+        let mut offset = name_len;
+
+        if rest[offset..].starts_with('.') {
+            let args_len = rest[offset + 1..]
+                .bytes()
+                .take_while(|&byte| is_name(byte) || matches!(byte, b'=' | b',' | b'.' | b'-'))
+                .count();
+
+            for arg in rest[offset + 1..offset + 1 + args_len].split(',') {
+                let (key, value) = match arg.split_once('=') {
+                    Some((key, value)) => (key, Some(Cow::Borrowed(value))),
+                    None => (arg, None),
+                };
+
+                if key.is_empty() || !key.bytes().all(is_name) {
+                    return None;
+                }
+
+                args.push((Cow::Borrowed(key), value));
+            }
+
+            offset += 1 + args_len;
+        }
+
+        if !rest[offset..].starts_with(' ') {
+            return None;
+        }
+
+        let content_start = pos + 2 + offset + 1;
+        let (children, end) = self.parse_inline(content_start, Some("]"), in_link, depth)?;
+
+        let function = MfmFunction {
+            name: Cow::Borrowed(name),
+            args,
+            children,
+        };
+
+        Some((Element::MfmFunction(function), end + 1))
+    }
+
+    /// `<plain>`, `<center>`, `<small>`, `<b>`, `<i>` and `<s>`
+    fn tag(&self, pos: usize, in_link: bool, depth: usize) -> Parsed<'a> {
+        let rest = &self.text[pos..];
+
+        if let Some(content) = rest.strip_prefix("<plain>") {
+            let len = content.find("</plain>")?;
+            let content_start = pos + "<plain>".len();
+
+            return Some((
+                text(&self.text[content_start..content_start + len]),
+                content_start + len + "</plain>".len(),
+            ));
+        }
+
+        if rest.starts_with("<center>") {
+            if !self.is_line_start(pos) {
+                return None;
+            }
+
+            let (element, end) = self.wrapped(
+                pos,
+                "<center>",
+                "</center>",
+                FormattingKind::Center,
+                in_link,
+                depth,
+            )?;
+
+            // Centered content has to end its line
+            return (end == self.line_end(end)).then(|| (element, self.skip_line_break(end)));
+        }
+
+        [
+            ("<small>", "</small>", FormattingKind::Small),
+            ("<b>", "</b>", FormattingKind::Strong),
+            ("<i>", "</i>", FormattingKind::Emphasis),
+            ("<s>", "</s>", FormattingKind::Strikethrough),
+        ]
+        .into_iter()
+        .find(|(open, ..)| rest.starts_with(open))
+        .and_then(|(open, close, kind)| self.wrapped(pos, open, close, kind, in_link, depth))
+    }
+
+    /// Inline content wrapped into an opening and a closing delimiter
+    fn wrapped(
+        &self,
+        pos: usize,
+        open: &str,
+        close: &str,
+        kind: FormattingKind<'a>,
+        in_link: bool,
+        depth: usize,
+    ) -> Parsed<'a> {
+        let content_start = pos + open.len();
+        let (children, end) = self.parse_inline(content_start, Some(close), in_link, depth)?;
+        if end == content_start {
+            return None;
+        }
+
+        Some((formatting(kind, children), end + close.len()))
+    }
+
+    /// `*italic*`, `_italic_` and `__bold__`
+    ///
+    /// Their content is restricted to ASCII letters, digits and spaces, as in Misskey
+    fn simple_emphasis(&self, pos: usize) -> Parsed<'a> {
+        let bytes = self.text.as_bytes();
+        let delimiter = bytes[pos];
+        if pos > 0 && bytes[pos - 1].is_ascii_alphanumeric() {
+            return None;
+        }
+
+        let (delimiter_len, kind) = if delimiter == b'_' && self.text[pos..].starts_with("__") {
+            (2, FormattingKind::Strong)
+        } else {
+            (1, FormattingKind::Emphasis)
+        };
+
+        let content_start = pos + delimiter_len;
+        let content_len = bytes[content_start..]
+            .iter()
+            .take_while(|&&byte| byte.is_ascii_alphanumeric() || byte == b' ' || byte == b'\t')
+            .count();
+
+        let content_end = content_start + content_len;
+        let closing = &bytes[content_end..];
+        if content_len == 0 || closing.len() < delimiter_len {
+            return None;
+        }
+
+        let closed = closing[..delimiter_len]
+            .iter()
+            .all(|&byte| byte == delimiter);
+        let end = content_end + delimiter_len;
+        if !closed || bytes.get(end).is_some_and(u8::is_ascii_alphanumeric) {
+            return None;
+        }
+
+        let children = vec![text(&self.text[content_start..content_end])];
+        Some((formatting(kind, children), end))
+    }
+
+    /// `~~strikethrough~~`, which can't span multiple lines
+    fn strikethrough(&self, pos: usize, in_link: bool, depth: usize) -> Parsed<'a> {
+        let content_end = pos + 2 + self.text[pos + 2..].find("~~")?;
+        if self.text[pos..content_end].contains('\n') {
+            return None;
+        }
+
+        self.wrapped(
+            pos,
+            "~~",
+            "~~",
+            FormattingKind::Strikethrough,
+            in_link,
+            depth,
+        )
+    }
+
+    /// Content that isn't parsed any further, ending on the same line
+    fn raw_inline(
+        &self,
+        pos: usize,
+        open: &str,
+        close: &str,
+        kind: FormattingKind<'a>,
+    ) -> Parsed<'a> {
+        let content_start = pos + open.len();
+        let len = self.text[content_start..].find(close)?;
+        let content = &self.text[content_start..content_start + len];
+        if content.is_empty() || content.contains('\n') {
+            return None;
+        }
+
+        Some((
+            formatting(kind, vec![text(content)]),
+            content_start + len + close.len(),
+        ))
+    }
+
+    /// `` `code` ``
+    fn inline_code(&self, pos: usize) -> Parsed<'a> {
+        self.raw_inline(pos, "`", "`", FormattingKind::Code)
+    }
+
+    /// `\(formula\)`
+    fn math_inline(&self, pos: usize) -> Parsed<'a> {
+        self.raw_inline(pos, "\\(", "\\)", FormattingKind::Math { block: false })
+    }
+
+    /// Content that isn't parsed any further, spanning whole lines
+    ///
+    /// The opening delimiter ends its line, the closing delimiter sits on a line of its own
+    fn raw_block(&self, open_line_end: usize, close: &str, kind: FormattingKind<'a>) -> Parsed<'a> {
+        let content_start = self.skip_line_break(open_line_end);
+        if content_start == open_line_end {
+            return None;
+        }
+
+        let mut line_start = content_start;
+        while line_start < self.text.len() {
+            let line_end = self.line_end(line_start);
+            if self.text[line_start..line_end].trim_end() == close {
+                let content = self.text[content_start..line_start]
+                    .strip_suffix('\n')
+                    .unwrap_or_default();
+
+                let children = if content.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![text(content)]
+                };
+
+                return Some((formatting(kind, children), self.skip_line_break(line_end)));
+            }
+
+            line_start = self.skip_line_break(line_end);
+            if line_start == line_end {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// ```` ```language ```` blocks
+    fn code_block(&self, pos: usize) -> Parsed<'a> {
+        let line_end = self.line_end(pos);
+        let language = self.text[pos + 3..line_end].trim();
+        if language.contains('`') {
+            return None;
+        }
+
+        let kind = FormattingKind::CodeBlock {
+            language: (!language.is_empty()).then_some(Cow::Borrowed(language)),
+        };
+
+        self.raw_block(line_end, "```", kind)
+    }
+
+    /// `\[formula\]` blocks
+    fn math_block(&self, pos: usize) -> Parsed<'a> {
+        let line_end = self.line_end(pos);
+        if !self.text[pos + 2..line_end].trim().is_empty() {
+            return None;
+        }
+
+        self.raw_block(line_end, "\\]", FormattingKind::Math { block: true })
+    }
+
+    /// `[label](https://example.com)` and `?[label](https://example.com)`
+    ///
+    /// `label_start` is the position right after the opening bracket
+    fn link(&self, label_start: usize, depth: usize) -> Parsed<'a> {
+        let (children, label_end) = self.parse_inline(label_start, Some("]"), true, depth)?;
+
+        let rest = self.text[label_end..].strip_prefix("](")?;
+        let (href, href_len) = if let Some(inner) = rest.strip_prefix('<') {
+            let len = inner.find('>')?;
+            (&inner[..len], len + 2)
+        } else {
+            let len = rest.find(|c: char| c == ')' || c.is_whitespace())?;
+            (&rest[..len], len)
+        };
+
+        if !(href.starts_with("https://") || href.starts_with("http://")) {
+            return None;
+        }
+
+        let end = label_end + 2 + href_len;
+        if !self.text[end..].starts_with(')') {
+            return None;
+        }
+
+        let kind = FormattingKind::Link {
+            href: Cow::Borrowed(href),
+        };
+
+        Some((formatting(kind, children), end + 1))
+    }
+
+    /// Consecutive lines starting with `>`
+    fn quote(&self, pos: usize, depth: usize) -> Parsed<'a> {
+        let mut content = String::new();
+        let mut line_start = pos;
+
+        while let Some(line) = self.text[line_start..self.line_end(line_start)].strip_prefix('>') {
+            content.push_str(line.strip_prefix(' ').unwrap_or(line));
+            content.push('\n');
+
+            let line_end = self.line_end(line_start);
+            line_start = self.skip_line_break(line_end);
+            if line_start == line_end {
+                break;
+            }
+        }
+
+        let content = content.trim_end_matches('\n');
+        if content.trim().is_empty() {
+            return None;
+        }
+
+        // The quoted lines aren't contiguous in the source, so the content has to be owned
+        let children = Parser::new(content)
+            .parse(depth)
+            .into_iter()
+            .map(Element::into_owned)
+            .collect();
+
+        Some((formatting(FormattingKind::BlockQuote, children), line_start))
+    }
+
+    /// `query Search` on a line of its own
+    fn search(&self, pos: usize) -> Parsed<'a> {
+        let line_end = self.line_end(pos);
+        let line = &self.text[pos..line_end];
+
+        let (query, suffix) = line.rsplit_once([' ', '\u{3000}'])?;
+        let is_search = SEARCH_SUFFIXES
+            .iter()
+            .any(|search| search.eq_ignore_ascii_case(suffix));
+
+        if !is_search || query.trim().is_empty() {
+            return None;
+        }
+
+        let kind = FormattingKind::Search {
+            query: Cow::Borrowed(query),
+        };
+
+        Some((
+            formatting(kind, vec![text(line)]),
+            self.skip_line_break(line_end),
+        ))
+    }
+}
@@ -0,0 +1,131 @@
+//!
+//! Building blocks for rendering HTML that is safe to embed into a page
+//!
+
+use std::fmt;
+
+/// Allowlist consulted when rendering safely
+///
+/// Tags and attribute names are compared ASCII case-insensitively
+#[derive(Clone, Copy, Debug)]
+pub struct Allowlist<'a> {
+    /// Allowed tags together with the attributes allowed on them
+    pub tags: &'a [(&'a str, &'a [&'a str])],
+
+    /// Allowed schemes of URLs in `href` and `src` attributes
+    ///
+    /// Relative URLs are always allowed
+    pub url_schemes: &'a [&'a str],
+}
+
+impl Allowlist<'_> {
+    /// Is the tag allowed?
+    #[must_use]
+    pub fn allows_tag(&self, tag: &str) -> bool {
+        self.attributes(tag).is_some()
+    }
+
+    /// Is the attribute allowed on the tag, with this value?
+    ///
+    /// URLs in `href` and `src` attributes are additionally checked against the allowed schemes
+    #[must_use]
+    pub fn allows_attribute(&self, tag: &str, name: &str, value: &str) -> bool {
+        let Some(attributes) = self.attributes(tag) else {
+            return false;
+        };
+
+        if !attributes
+            .iter()
+            .any(|attribute| attribute.eq_ignore_ascii_case(name))
+        {
+            return false;
+        }
+
+        if name.eq_ignore_ascii_case("href") || name.eq_ignore_ascii_case("src") {
+            self.allows_url(value)
+        } else {
+            true
+        }
+    }
+
+    /// Does the URL either have no scheme or an allowed one?
+    #[must_use]
+    pub fn allows_url(&self, url: &str) -> bool {
+        let url = url.trim();
+        let scheme_end = url.find([':', '/', '?', '#']);
+
+        match scheme_end {
+            Some(idx) if url.as_bytes()[idx] == b':' => {
+                let scheme = &url[..idx];
+                self.url_schemes
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+            }
+            _ => true,
+        }
+    }
+
+    fn attributes(&self, tag: &str) -> Option<&[&str]> {
+        self.tags
+            .iter()
+            .find(|(allowed, _)| allowed.eq_ignore_ascii_case(tag))
+            .map(|(_, attributes)| *attributes)
+    }
+}
+
+impl Default for Allowlist<'static> {
+    fn default() -> Self {
+        DEFAULT_ALLOWLIST
+    }
+}
+
+/// Allowlist covering the HTML subset commonly used in statuses across the fediverse
+pub const DEFAULT_ALLOWLIST: Allowlist<'static> = Allowlist {
+    tags: &[
+        ("a", &["href", "rel", "class", "translate"]),
+        ("b", &[]),
+        ("blockquote", &[]),
+        ("br", &[]),
+        ("code", &["class"]),
+        ("del", &[]),
+        ("em", &[]),
+        ("i", &[]),
+        ("li", &[]),
+        ("ol", &["start", "reversed"]),
+        ("p", &[]),
+        ("pre", &[]),
+        ("span", &["class", "translate"]),
+        ("strong", &[]),
+        ("u", &[]),
+        ("ul", &[]),
+    ],
+    url_schemes: &["http", "https", "mailto", "xmpp", "magnet"],
+};
+
+/// Writer adapter escaping everything written through it for use in HTML text and attribute values
+pub struct Escape<'a, W: ?Sized>(pub &'a mut W);
+
+impl<W> fmt::Write for Escape<'_, W>
+where
+    W: fmt::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut last = 0;
+        for (idx, byte) in s.bytes().enumerate() {
+            let escaped = match byte {
+                b'&' => "&amp;",
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'"' => "&quot;",
+                b'\'' => "&#39;",
+                _ => continue,
+            };
+
+            self.0.write_str(&s[last..idx])?;
+            self.0.write_str(escaped)?;
+            last = idx + 1;
+        }
+
+        self.0.write_str(&s[last..])
+    }
+}
@@ -0,0 +1,52 @@
+use post_process::{CountingPolicy, count_characters};
+
+#[test]
+fn count_graphemes() {
+    // Family emoji made up of seven code points, flag made up of two
+    let text = "héllo 👨‍👩‍👧‍👦 🇯🇵";
+
+    assert_eq!(count_characters(text, CountingPolicy::MASTODON), 9);
+    assert_eq!(count_characters(text, CountingPolicy::VERBATIM), 9);
+}
+
+#[test]
+fn count_links() {
+    let text = "read https://example.com/a/very/long/path/that/goes/on/and/on";
+
+    assert_eq!(count_characters(text, CountingPolicy::MASTODON), 5 + 23);
+    assert_eq!(
+        count_characters(text, CountingPolicy::VERBATIM),
+        text.chars().count()
+    );
+}
+
+#[test]
+fn count_schemeless_links() {
+    let text = "read www.example.com";
+
+    assert_eq!(
+        count_characters(text, CountingPolicy::MASTODON),
+        text.chars().count()
+    );
+}
+
+#[test]
+fn count_mentions() {
+    let text = "@真島@goro.org and @kiryu";
+
+    assert_eq!(count_characters(text, CountingPolicy::MASTODON), 3 + 5 + 6);
+    assert_eq!(
+        count_characters(text, CountingPolicy::VERBATIM),
+        text.chars().count()
+    );
+}
+
+#[test]
+fn count_others_verbatim() {
+    let text = "#龍が如く0 :friday-night:";
+
+    assert_eq!(
+        count_characters(text, CountingPolicy::default()),
+        text.chars().count()
+    );
+}
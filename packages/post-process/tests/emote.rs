@@ -1,5 +1,6 @@
 use crate::util::parse_to_test_output;
-use std::fs;
+use post_process::{Emote, Render, extract};
+use std::{borrow::Cow, fs};
 
 mod util;
 
@@ -10,3 +11,187 @@ fn parse_emote() {
         insta::assert_debug_snapshot!(parse_to_test_output(&post));
     });
 }
+
+fn render(emote: &Emote<'_>) -> String {
+    let mut out = String::new();
+    emote.render(&mut out);
+    out
+}
+
+fn remote(shortcode: &'static str, domain: &'static str) -> Emote<'static> {
+    Emote {
+        shortcode: Cow::Borrowed(shortcode),
+        domain: Some(Cow::Borrowed(domain)),
+    }
+}
+
+#[test]
+fn domain_round_trip() {
+    let cases = [
+        (
+            remote("blobcat", "example.com"),
+            ":blobcat__R1_example_com:",
+        ),
+        (
+            remote("blobcat", "my-server.example"),
+            ":blobcat__R1_myHserver_example:",
+        ),
+        (
+            remote("blobcat", "my_server.example"),
+            ":blobcat__R1_myUserver_example:",
+        ),
+        (
+            remote("blob_cat", "a-b_c.example"),
+            ":blob_cat__R1_aHbUc_example:",
+        ),
+        (
+            remote("blob__cat", "example.com"),
+            ":blob__cat__R1_example_com:",
+        ),
+        (
+            remote("blob__R1_cat", "example.com"),
+            ":blob__R1_cat__R1_example_com:",
+        ),
+        (
+            remote("blobcat_", "example.com"),
+            ":blobcat___R1_example_com:",
+        ),
+        (
+            remote("blobcat", "Example.COM"),
+            ":blobcat__R1_example_com:",
+        ),
+    ];
+
+    for (emote, rendered) in cases {
+        assert_eq!(render(&emote), rendered);
+
+        let shortcode = rendered.trim_matches(':');
+        let parsed = Emote::from_shortcode(shortcode);
+        assert_eq!(parsed.shortcode, emote.shortcode);
+        assert_eq!(
+            parsed.domain.as_deref(),
+            emote
+                .domain
+                .as_deref()
+                .map(str::to_ascii_lowercase)
+                .as_deref()
+        );
+    }
+}
+
+#[test]
+fn distinct_domains_render_differently() {
+    let rendered = [
+        render(&remote("blobcat", "my-server.example")),
+        render(&remote("blobcat", "my_server.example")),
+        render(&remote("blobcat", "my.server.example")),
+        render(&remote("blobcat_my", "server.example")),
+    ];
+
+    for (idx, lhs) in rendered.iter().enumerate() {
+        for rhs in &rendered[idx + 1..] {
+            assert_ne!(lhs, rhs);
+        }
+    }
+}
+
+#[test]
+fn internationalised_domain() {
+    let emote = remote("blobcat", "bücher.example");
+    assert_eq!(render(&emote), ":blobcat__R1_xnHHbcherHkva_example:");
+
+    let parsed = Emote::from_shortcode("blobcat__R1_xnHHbcherHkva_example");
+    assert_eq!(parsed.domain.as_deref(), Some("xn--bcher-kva.example"));
+}
+
+#[test]
+fn unencodable_domain() {
+    assert_eq!(
+        render(&remote("blobcat", "localhost")),
+        ":blobcat@localhost:"
+    );
+    assert_eq!(
+        render(&remote("blobcat", "example.com:8080")),
+        ":blobcat@example.com:8080:"
+    );
+}
+
+#[test]
+fn local_shortcodes() {
+    for shortcode in [
+        "blobcat",
+        "blob_cat",
+        "blob__cat",
+        "__R1_example_com",
+        "blobcat__R1_Example_com",
+        "blobcat__R1_example__com",
+    ] {
+        assert_eq!(Emote::from_shortcode(shortcode).domain, None, "{shortcode}");
+        assert_eq!(
+            render(&Emote::from_shortcode(shortcode)),
+            format!(":{shortcode}:")
+        );
+    }
+}
+
+#[test]
+fn legacy_shortcodes() {
+    // Rendered before the encoding was versioned, when both '.' and '-' were replaced by '_'
+    for shortcode in [
+        "blobcat__my_instance_com",
+        "blobcat__example_com",
+        "blob__cat__example_com",
+    ] {
+        let emote = Emote::from_shortcode(shortcode);
+        assert_eq!(emote.shortcode, shortcode);
+        assert_eq!(emote.domain, None, "{shortcode}");
+        assert_eq!(render(&emote), format!(":{shortcode}:"));
+    }
+}
+
+#[test]
+fn local_shortcodes_with_domain_like_suffix() {
+    for shortcode in [
+        "blob__example_com",
+        "party__myHserver_example",
+        "cat__at_home",
+    ] {
+        assert!(!Emote::is_reserved_shortcode(shortcode), "{shortcode}");
+
+        let emote = Emote::from_shortcode(shortcode);
+        assert_eq!(emote.shortcode, shortcode);
+        assert_eq!(emote.domain, None, "{shortcode}");
+        assert_eq!(render(&emote), format!(":{shortcode}:"));
+    }
+}
+
+#[test]
+fn reserved_shortcodes() {
+    for shortcode in ["blobcat__R1_example_com", "__R1_", "blob__R1_cat"] {
+        assert!(Emote::is_reserved_shortcode(shortcode), "{shortcode}");
+    }
+
+    for shortcode in ["blobcat", "blob__cat", "blobcat__r1_example_com"] {
+        assert!(!Emote::is_reserved_shortcode(shortcode), "{shortcode}");
+    }
+}
+
+#[test]
+fn parse_encoded_emote() {
+    let emotes: Vec<_> = extract("reply :blobcat__R1_myHserver_example: and :blobcat:")
+        .emotes
+        .into_iter()
+        .map(|occurrence| occurrence.value)
+        .collect();
+
+    assert_eq!(
+        emotes,
+        [
+            remote("blobcat", "my-server.example"),
+            Emote {
+                shortcode: Cow::Borrowed("blobcat"),
+                domain: None,
+            },
+        ]
+    );
+}
@@ -0,0 +1,91 @@
+use post_process::{Emote, Extracted, Hashtag, Link, Mention, Occurrence};
+use pretty_assertions::assert_eq;
+use std::borrow::Cow;
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn extract_deduplicated() {
+    let text = "@alice@Example.com #Rust :ferris: https://example.com \
+        @alice@example.com #rust #RUST :ferris: :ferris@example.com: @bob https://example.com ＃ｒｕｓｔ";
+
+    assert_eq!(
+        post_process::extract(text),
+        Extracted {
+            emotes: vec![
+                Occurrence {
+                    value: Emote {
+                        shortcode: Cow::Borrowed("ferris"),
+                        domain: None,
+                    },
+                    spans: vec![25..33, 85..93],
+                },
+                Occurrence {
+                    value: Emote {
+                        shortcode: Cow::Borrowed("ferris"),
+                        domain: Some(Cow::Borrowed("example.com")),
+                    },
+                    spans: vec![94..114],
+                },
+            ],
+            hashtags: vec![Occurrence {
+                value: Hashtag::new("Rust"),
+                spans: vec![19..24, 73..78, 79..84, 140..155],
+            }],
+            links: vec![Occurrence {
+                value: Link {
+                    content: Cow::Borrowed("https://example.com"),
+                },
+                spans: vec![34..53, 120..139],
+            }],
+            mentions: vec![
+                Occurrence {
+                    value: Mention {
+                        username: Cow::Borrowed("alice"),
+                        domain: Some(Cow::Borrowed("Example.com")),
+                    },
+                    spans: vec![0..18, 54..72],
+                },
+                Occurrence {
+                    value: Mention {
+                        username: Cow::Borrowed("bob"),
+                        domain: None,
+                    },
+                    spans: vec![115..119],
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn extract_nothing() {
+    assert_eq!(
+        post_process::extract("just some text"),
+        Extracted::default()
+    );
+}
+
+#[test]
+fn extract_mentions_case_insensitively() {
+    let extracted = post_process::extract("@Foo @foo @FOO@Example.com @foo@example.com");
+
+    assert_eq!(
+        extracted.mentions,
+        [
+            Occurrence {
+                value: Mention {
+                    username: Cow::Borrowed("Foo"),
+                    domain: None,
+                },
+                spans: vec![0..4, 5..9],
+            },
+            Occurrence {
+                value: Mention {
+                    username: Cow::Borrowed("FOO"),
+                    domain: Some(Cow::Borrowed("Example.com")),
+                },
+                spans: vec![10..26, 27..43],
+            },
+        ]
+    );
+}
@@ -1,4 +1,5 @@
 use crate::util::parse_to_test_output;
+use post_process::{extract, normalise_hashtag};
 use std::fs;
 
 mod util;
@@ -26,3 +27,57 @@ fn parse_hashtag() {
         insta::assert_debug_snapshot!(parse_to_test_output(&post));
     });
 }
+
+#[test]
+fn normalisation() {
+    insta::glob!("input/hashtag/normalisation_*", |path| {
+        let post = fs::read_to_string(path).unwrap();
+        let keys: Vec<_> = extract(&post)
+            .hashtags
+            .into_iter()
+            .map(|hashtag| (hashtag.value.content, hashtag.value.key))
+            .collect();
+
+        insta::assert_debug_snapshot!((parse_to_test_output(&post), keys));
+    });
+}
+
+#[test]
+fn normalise() {
+    assert_eq!(normalise_hashtag("Rust").as_deref(), Some("rust"));
+    assert_eq!(normalise_hashtag("ｒｕｓｔ").as_deref(), Some("rust"));
+    assert_eq!(
+        normalise_hashtag("Ｒｕｓｔ_Lang").as_deref(),
+        Some("rust_lang")
+    );
+    assert_eq!(normalise_hashtag("café").as_deref(), Some("café"));
+    assert_eq!(normalise_hashtag("cafe\u{301}").as_deref(), Some("café"));
+    assert_eq!(
+        normalise_hashtag("tag-with-hyphen").as_deref(),
+        Some("tagwithhyphen")
+    );
+    assert_eq!(normalise_hashtag("rust\u{b7}"), None);
+    assert_eq!(normalise_hashtag("2024"), None);
+    assert_eq!(normalise_hashtag("２０２４"), None);
+    assert_eq!(normalise_hashtag(""), None);
+}
+
+#[test]
+#[allow(clippy::single_range_in_vec_init)]
+fn trailing_separators() {
+    let text = "#rust\u{b7} #日本\u{30fb}語\u{30fb} #a\u{200c}";
+    let hashtags: Vec<_> = extract(text)
+        .hashtags
+        .into_iter()
+        .map(|hashtag| (hashtag.value.content.into_owned(), hashtag.spans))
+        .collect();
+
+    assert_eq!(
+        hashtags,
+        [
+            ("rust".to_owned(), vec![0..5]),
+            ("日本\u{30fb}語".to_owned(), vec![8..21]),
+            ("a".to_owned(), vec![25..27]),
+        ]
+    );
+}
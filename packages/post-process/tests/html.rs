@@ -0,0 +1,157 @@
+use futures_executor::block_on;
+use post_process::{DEFAULT_ALLOWLIST, Element, Hashtag, Html, Link, Mention, html};
+use pretty_assertions::assert_eq;
+use std::{borrow::Cow, fs, future};
+
+#[test]
+fn render_html() {
+    insta::glob!("input/html/*", |path| {
+        let content = fs::read_to_string(path).unwrap();
+        let rendered = block_on(html::transform_safe(
+            &content,
+            |item| future::ready(Ok(item)),
+            &DEFAULT_ALLOWLIST,
+        ))
+        .unwrap();
+
+        insta::assert_snapshot!(rendered);
+    });
+}
+
+#[test]
+fn recognise_microformats() {
+    let content = r#"<span class="h-card"><a href="https://example.com/@alice" class="u-url mention">@<span>alice</span></a></span><a href="https://example.com/tags/fox" class="mention hashtag" rel="tag">#<span>fox</span></a><a href="https://example.com/"><span class="invisible">https://</span><span>example.com/</span></a>"#;
+
+    assert_eq!(
+        html::parse(content),
+        [
+            Element::Mention(Mention {
+                username: Cow::Borrowed("alice"),
+                domain: Some(Cow::Borrowed("example.com")),
+            }),
+            Element::Hashtag(Hashtag::new("fox")),
+            Element::Link(Link {
+                content: Cow::Borrowed("https://example.com/"),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn transform_mentions() {
+    let content = r#"<p><a href="https://example.com/@bob" class="u-url mention">@bob@example.com</a> hi</p>"#;
+    let transformed = block_on(html::transform_safe(
+        content,
+        |elem| async move {
+            let transformed = match elem {
+                Element::Mention(mention) => Element::Html(Html {
+                    tag: Cow::Borrowed("a"),
+                    attributes: vec![(
+                        Cow::Borrowed("href"),
+                        Cow::Owned(format!(
+                            "https://kitsune.example/@{}@{}",
+                            mention.username,
+                            mention.domain.as_deref().unwrap_or_default()
+                        )),
+                    )],
+                    content: Box::new(Element::Mention(mention)),
+                }),
+                elem => elem,
+            };
+
+            Ok(transformed)
+        },
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        transformed,
+        "<p><a href=\"https://kitsune.example/@bob@example.com\">@bob@example.com</a> hi</p>"
+    );
+}
+
+#[test]
+fn deep_nesting() {
+    let text = "<b>".repeat(50_000) + "deep" + &"</b>".repeat(50_000) + " shallow";
+    let rendered = block_on(html::transform_safe(
+        &text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        rendered,
+        "<strong>".repeat(32) + "deep" + &"</strong>".repeat(32) + " shallow"
+    );
+
+    // Unclosed tags
+    let text = "<i>".repeat(50_000) + "deep";
+    let elements = html::parse(&text);
+    assert_eq!(elements.len(), 1);
+}
+
+#[test]
+fn deep_nesting_dropped_content() {
+    let text = "<b>".repeat(100) + "<svg><i>hidden</i></svg>visible";
+    let rendered = block_on(html::transform_safe(
+        &text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert!(!rendered.contains("hidden"));
+    assert!(rendered.contains("visible"));
+}
+
+#[test]
+fn unterminated_references() {
+    let text = "&".repeat(200_000) + "&amp;";
+    let rendered = block_on(html::transform_safe(
+        &text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(rendered, "&amp;".repeat(200_001));
+}
+
+#[test]
+fn raw_text_end_tag() {
+    let text = "<p>a<style>b</p></STYLE>c</p>";
+    let rendered = block_on(html::transform_safe(
+        text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(rendered, "<p>ac</p>");
+
+    let text = "<title></title>".repeat(20_000) + "<p>visible</p>";
+    let rendered = block_on(html::transform_safe(
+        &text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(rendered, "<p>visible</p>");
+}
+
+#[test]
+fn dropped_block_keeps_paragraph_open() {
+    // The paragraph is opened at the maximum depth, so the SVG is flattened
+    let text = "<b>".repeat(31) + "<p>before<svg><p>hidden</p></svg>after</p>";
+    let rendered = block_on(html::transform_safe(
+        &text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert!(rendered.contains("<p>beforeafter</p>"), "{rendered}");
+}
@@ -1,8 +1,24 @@
 use crate::util::parse_to_test_output;
+use logos::Lexer;
+use post_process::{LinkSchemes, PostElement, extract};
 use std::fs;
 
 mod util;
 
+/// Display form and target of every link in the text
+fn display_and_href(text: &str) -> Vec<(String, String)> {
+    extract(text)
+        .links
+        .into_iter()
+        .map(|link| {
+            (
+                link.value.display().into_owned(),
+                link.value.href().into_owned(),
+            )
+        })
+        .collect()
+}
+
 #[test]
 fn link_only() {
     insta::glob!("input/link/only_link_*", |path| {
@@ -10,3 +26,56 @@ fn link_only() {
         insta::assert_debug_snapshot!(parse_to_test_output(&link));
     });
 }
+
+#[test]
+fn balanced_parentheses() {
+    insta::glob!("input/link/parentheses_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        insta::assert_debug_snapshot!(parse_to_test_output(&text));
+    });
+}
+
+#[test]
+fn trailing_punctuation() {
+    insta::glob!("input/link/punctuation_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        insta::assert_debug_snapshot!(parse_to_test_output(&text));
+    });
+}
+
+#[test]
+fn default_schemes() {
+    insta::glob!("input/link/scheme_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        insta::assert_debug_snapshot!(parse_to_test_output(&text));
+    });
+}
+
+#[test]
+fn custom_schemes() {
+    insta::glob!("input/link/scheme_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        let schemes = LinkSchemes::new(["data", "gemini"]);
+        let links: Vec<_> = Lexer::<PostElement<'_>>::with_extras(&text, schemes)
+            .flatten()
+            .collect();
+
+        insta::assert_debug_snapshot!(links);
+    });
+}
+
+#[test]
+fn schemeless() {
+    insta::glob!("input/link/schemeless_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        insta::assert_debug_snapshot!((parse_to_test_output(&text), display_and_href(&text)));
+    });
+}
+
+#[test]
+fn internationalised_hostnames() {
+    insta::glob!("input/link/idn_*", |path| {
+        let text = fs::read_to_string(path).unwrap();
+        insta::assert_debug_snapshot!((parse_to_test_output(&text), display_and_href(&text)));
+    });
+}
@@ -0,0 +1,109 @@
+use futures_executor::block_on;
+use post_process::{DEFAULT_ALLOWLIST, Element, Html, markdown};
+use pretty_assertions::assert_eq;
+use std::{borrow::Cow, fs, future};
+
+#[test]
+fn render_markdown() {
+    insta::glob!("input/markdown/*", |path| {
+        let post = fs::read_to_string(path).unwrap();
+        let rendered =
+            block_on(markdown::transform(&post, |item| future::ready(Ok(item)))).unwrap();
+
+        insta::assert_snapshot!(rendered);
+    });
+}
+
+#[test]
+fn transform_outside_of_code() {
+    let text = "#rust is great, `#notatag` isn't a hashtag\n\n```\n#neither\n```";
+    let transformed = block_on(markdown::transform(text, |elem| async move {
+        let transformed = match elem {
+            Element::Hashtag(hashtag) => Element::Html(Html {
+                tag: Cow::Borrowed("a"),
+                attributes: vec![(
+                    Cow::Borrowed("href"),
+                    Cow::Owned(format!("https://example.com/hashtag/{}", hashtag.content)),
+                )],
+                content: Box::new(Element::Hashtag(hashtag)),
+            }),
+            elem => elem,
+        };
+
+        Ok(transformed)
+    }))
+    .unwrap();
+
+    assert_eq!(
+        transformed,
+        "<p><a href=\"https://example.com/hashtag/rust\">#rust</a> is great, <code>#notatag</code> isn't a hashtag</p><pre><code>#neither</code></pre>"
+    );
+}
+
+#[test]
+fn render_markdown_safe() {
+    let text = "*<script>* [click](javascript:alert(1))\n\n```html\n<b>\"bold\"</b>\n```";
+    let rendered = block_on(markdown::transform_safe(
+        text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        rendered,
+        "<p><em>&lt;script&gt;</em> <a>click</a></p><pre><code class=\"language-html\">&lt;b&gt;&quot;bold&quot;&lt;/b&gt;</code></pre>"
+    );
+}
+
+#[test]
+fn escape_attributes() {
+    let text = "[x](<https://a\" onmouseover=\"alert(1)>)";
+    let rendered = block_on(markdown::transform(text, |item| future::ready(Ok(item)))).unwrap();
+
+    assert_eq!(
+        rendered,
+        "<p><a href=\"https://a&quot; onmouseover=&quot;alert(1)\">x</a></p>"
+    );
+}
+
+#[test]
+fn list_marker_followed_by_unicode_whitespace() {
+    for text in ["-\u{3000}", "1.\u{a0}", "-\u{3000}item", "1.\u{a0}item"] {
+        let rendered = block_on(markdown::transform(text, |item| future::ready(Ok(item)))).unwrap();
+
+        assert_eq!(rendered, format!("<p>{}</p>", text.trim()));
+    }
+}
+
+#[test]
+fn deep_nesting() {
+    for (marker, tag) in [("> ", "<blockquote>"), ("- ", "<ul>")] {
+        let text = marker.repeat(50_000) + "deep";
+        let rendered =
+            block_on(markdown::transform(&text, |item| future::ready(Ok(item)))).unwrap();
+
+        assert_eq!(rendered.matches(tag).count(), 20, "{marker:?}");
+        assert!(rendered.contains("deep"));
+    }
+}
+
+#[test]
+fn deep_emphasis() {
+    let text = "*a ".repeat(20_000) + "x" + &" a*".repeat(20_000);
+    let rendered = block_on(markdown::transform(&text, |item| future::ready(Ok(item)))).unwrap();
+
+    assert_eq!(rendered.matches("<em>").count(), 20);
+    assert!(rendered.contains('x'));
+}
+
+#[test]
+fn unclosed_emphasis() {
+    for opener in ["*a ", "**a ", "_a "] {
+        let text = opener.repeat(50_000) + "a*";
+        let rendered =
+            block_on(markdown::transform(&text, |item| future::ready(Ok(item)))).unwrap();
+
+        assert!(rendered.starts_with(&format!("<p>{opener}")), "{opener:?}");
+    }
+}
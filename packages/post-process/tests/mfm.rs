@@ -0,0 +1,102 @@
+use futures_executor::block_on;
+use post_process::{
+    DEFAULT_ALLOWLIST, Element, Formatting, FormattingKind, MfmFunction, Text, mfm,
+};
+use pretty_assertions::assert_eq;
+use std::{borrow::Cow, fs, future};
+
+#[test]
+fn render_mfm() {
+    insta::glob!("input/mfm/*", |path| {
+        let post = fs::read_to_string(path).unwrap();
+        let rendered = block_on(mfm::transform(&post, |item| future::ready(Ok(item)))).unwrap();
+
+        insta::assert_snapshot!(rendered);
+    });
+}
+
+#[test]
+fn parse_function() {
+    let elements = mfm::parse("$[spin.speed=2s,x Text]");
+
+    assert_eq!(
+        elements,
+        [Element::MfmFunction(MfmFunction {
+            name: Cow::Borrowed("spin"),
+            args: vec![
+                (Cow::Borrowed("speed"), Some(Cow::Borrowed("2s"))),
+                (Cow::Borrowed("x"), None),
+            ],
+            children: vec![Element::Text(Text {
+                content: Cow::Borrowed("Text"),
+            })],
+        })]
+    );
+}
+
+#[test]
+fn parse_unterminated() {
+    let text = "$[x2 <b><i>".repeat(500);
+    let elements = mfm::parse(&text);
+
+    assert_eq!(
+        elements,
+        [Element::Text(Text {
+            content: Cow::Borrowed(&text),
+        })]
+    );
+}
+
+#[test]
+fn nesting_limit() {
+    let text = format!("{}deep{}", "$[x2 ".repeat(30), "]".repeat(30));
+    let elements = mfm::parse(&text);
+
+    let mut depth = 0;
+    let mut current = &elements;
+    while let [Element::MfmFunction(function), ..] = current.as_slice() {
+        depth += 1;
+        current = &function.children;
+    }
+
+    assert_eq!(depth, 20);
+}
+
+#[test]
+fn render_mfm_safe() {
+    let text = "$[x2 <b>\"hi\"</b>]\n<center>centered</center>";
+    let rendered = block_on(mfm::transform_safe(
+        text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(rendered, "<i><strong>&quot;hi&quot;</strong></i>centered");
+}
+
+#[test]
+fn escape_attributes() {
+    let text = "[x](https://a\"onmouseover=\"alert(1))";
+    let rendered = block_on(mfm::transform(text, |item| future::ready(Ok(item)))).unwrap();
+
+    assert_eq!(
+        rendered,
+        "<a href=\"https://a&quot;onmouseover=&quot;alert(1\">x</a>)"
+    );
+}
+
+#[test]
+fn quote_is_owned() {
+    let elements = mfm::parse("> quoted");
+
+    assert_eq!(
+        elements,
+        [Element::Formatting(Formatting {
+            kind: FormattingKind::BlockQuote,
+            children: vec![Element::Text(Text {
+                content: Cow::Borrowed("quoted"),
+            })],
+        })]
+    );
+}
@@ -0,0 +1,113 @@
+use futures_executor::block_on;
+use post_process::{Allowlist, DEFAULT_ALLOWLIST, Element, Html, Render};
+use pretty_assertions::assert_eq;
+use std::{borrow::Cow, future};
+
+fn link<'a>(href: &'a str, content: Element<'a>) -> Element<'a> {
+    Element::Html(Html {
+        tag: Cow::Borrowed("a"),
+        attributes: vec![
+            (Cow::Borrowed("href"), Cow::Borrowed(href)),
+            (Cow::Borrowed("onclick"), Cow::Borrowed("alert(1)")),
+        ],
+        content: Box::new(content),
+    })
+}
+
+#[test]
+fn escapes_text() {
+    let text = "<script>alert(\"hi\")</script> & @真島@goro.org";
+    let transformed = block_on(post_process::transform_safe(
+        text,
+        |item| future::ready(Ok(item)),
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        transformed,
+        "&lt;script&gt;alert(&quot;hi&quot;)&lt;/script&gt; &amp; @真島@goro.org"
+    );
+}
+
+#[test]
+fn drops_disallowed_attributes() {
+    let text = "#龍が如く0";
+    let transformed = block_on(post_process::transform_safe(
+        text,
+        |elem| async move {
+            Ok(match elem {
+                Element::Hashtag(hashtag) => link(
+                    "https://example.com/hashtag/\"quoted\"",
+                    Element::Hashtag(hashtag),
+                ),
+                elem => elem,
+            })
+        },
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        transformed,
+        "<a href=\"https://example.com/hashtag/&quot;quoted&quot;\">#龍が如く0</a>"
+    );
+}
+
+#[test]
+fn drops_disallowed_schemes() {
+    let text = "#龍が如く0";
+    let transformed = block_on(post_process::transform_safe(
+        text,
+        |elem| async move {
+            Ok(match elem {
+                Element::Hashtag(hashtag) => {
+                    link(" JavaScript:alert(1)", Element::Hashtag(hashtag))
+                }
+                elem => elem,
+            })
+        },
+        &DEFAULT_ALLOWLIST,
+    ))
+    .unwrap();
+
+    assert_eq!(transformed, "<a>#龍が如く0</a>");
+}
+
+#[test]
+fn strips_disallowed_tags() {
+    let element = Element::Html(Html {
+        tag: Cow::Borrowed("iframe"),
+        attributes: vec![(Cow::Borrowed("src"), Cow::Borrowed("https://example.com"))],
+        content: Box::new(link(
+            "/relative",
+            Element::Text(post_process::Text {
+                content: Cow::Borrowed("<b>"),
+            }),
+        )),
+    });
+
+    let mut out = String::new();
+    element.render_safe(&mut out, &DEFAULT_ALLOWLIST);
+    assert_eq!(out, "<a href=\"/relative\">&lt;b&gt;</a>");
+
+    let mut out = String::new();
+    let allowlist = Allowlist {
+        tags: &[],
+        url_schemes: &[],
+    };
+    element.render_safe(&mut out, &allowlist);
+    assert_eq!(out, "&lt;b&gt;");
+}
+
+#[test]
+fn allowlist_urls() {
+    let allowlist = Allowlist::default();
+
+    assert!(allowlist.allows_url("https://example.com"));
+    assert!(allowlist.allows_url("HTTPS://example.com"));
+    assert!(allowlist.allows_url("/tags/kitsune"));
+    assert!(allowlist.allows_url("?page=2"));
+    assert!(!allowlist.allows_url("javascript:alert(1)"));
+    assert!(!allowlist.allows_url("data:text/html,<b>"));
+}
@@ -1,7 +1,37 @@
 use futures_executor::block_on;
 use post_process::{Element, Html};
 use pretty_assertions::assert_eq;
-use std::{borrow::Cow, future};
+use std::{
+    borrow::Cow,
+    future,
+    num::NonZeroUsize,
+    pin::Pin,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll},
+};
+
+/// Future returning `Pending` once, so other transformations get the chance to start in the meantime
+#[derive(Default)]
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
 
 #[test]
 fn link_transformation() {
@@ -56,3 +86,78 @@ fn noop_transformation() {
 
     assert_eq!(text, transformed);
 }
+
+#[test]
+fn concurrent_transformation() {
+    let text = "@a@example.com @b@example.com #c :d: @e@example.com #f";
+    let in_flight = AtomicUsize::new(0);
+    let max_in_flight = AtomicUsize::new(0);
+
+    let transformed = block_on(post_process::transform_concurrent(
+        text,
+        |elem| {
+            let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+            let in_flight = &in_flight;
+            async move {
+                YieldNow::default().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                let transformed = match elem {
+                    Element::Mention(mention) => Element::Html(Html {
+                        tag: Cow::Borrowed("a"),
+                        attributes: Vec::new(),
+                        content: Box::new(Element::Mention(mention)),
+                    }),
+                    elem => elem,
+                };
+
+                Ok(transformed)
+            }
+        },
+        NonZeroUsize::new(2).unwrap(),
+    ))
+    .unwrap();
+
+    assert_eq!(
+        transformed,
+        "<a>@a@example.com</a> <a>@b@example.com</a> #c :d: <a>@e@example.com</a> #f"
+    );
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn transformation_order() {
+    let text = "@a@example.com #b :c: https://d.example";
+    let order = |limit| {
+        let order = Mutex::new(Vec::new());
+        block_on(post_process::transform_concurrent(
+            text,
+            |elem| {
+                order.lock().unwrap().push(format!("{elem:?}"));
+                future::ready(Ok(elem))
+            },
+            limit,
+        ))
+        .unwrap();
+
+        order.into_inner().unwrap()
+    };
+
+    let sequential = Mutex::new(Vec::new());
+    block_on(post_process::transform(text, |elem| {
+        sequential.lock().unwrap().push(format!("{elem:?}"));
+        future::ready(Ok(elem))
+    }))
+    .unwrap();
+    let sequential = sequential.into_inner().unwrap();
+
+    let mut forward = order(NonZeroUsize::new(4).unwrap());
+    assert!(forward[0].starts_with("Mention"));
+    assert_eq!(order(NonZeroUsize::MIN), sequential);
+
+    // Sequential transformations run from the last element to the first
+    forward.reverse();
+    assert_eq!(sequential, forward);
+}
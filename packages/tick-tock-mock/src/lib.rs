@@ -3,13 +3,27 @@
 
 use std::{
     cell::RefCell,
+    collections::HashMap,
+    mem,
     sync::{
-        Arc, Weak,
+        Arc, Mutex, Weak,
         atomic::{AtomicI64, Ordering},
     },
+    task::Waker,
     time::{Duration, SystemTime},
 };
 
+#[cfg(feature = "tokio")]
+mod sleep;
+#[cfg(feature = "tokio")]
+mod task;
+
+#[cfg(feature = "tokio")]
+pub use self::{
+    sleep::{Sleep, sleep, sleep_until},
+    task::spawn,
+};
+
 thread_local! {
     /// Thread-local clock
     ///
@@ -27,10 +41,68 @@ pub enum DeltaDirection {
     Sub,
 }
 
+/// Shared delta of a mockable clock
+#[derive(Default)]
+struct Delta {
+    nanos: AtomicI64,
+
+    /// ID handed out to the next timer
+    #[cfg(feature = "tokio")]
+    next_timer_id: std::sync::atomic::AtomicU64,
+
+    /// Wakers of the timers waiting for the clock to move, keyed by the ID of the timer
+    wakers: Mutex<HashMap<u64, Waker>>,
+}
+
+impl Delta {
+    #[inline]
+    fn load(&self) -> i64 {
+        self.nanos.load(Ordering::Acquire)
+    }
+
+    /// Allocate an ID for a timer to register its waker under
+    #[cfg(feature = "tokio")]
+    #[inline]
+    fn timer_id(&self) -> u64 {
+        self.next_timer_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Register the waker of a timer which is woken up the next time the delta changes
+    ///
+    /// Replaces the waker previously registered by the timer
+    #[cfg(feature = "tokio")]
+    #[inline]
+    fn register(&self, timer_id: u64, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap();
+        if !wakers
+            .get(&timer_id)
+            .is_some_and(|registered| registered.will_wake(waker))
+        {
+            wakers.insert(timer_id, waker.clone());
+        }
+    }
+
+    /// Remove the waker of a timer
+    #[cfg(feature = "tokio")]
+    #[inline]
+    fn deregister(&self, timer_id: u64) {
+        self.wakers.lock().unwrap().remove(&timer_id);
+    }
+
+    #[inline]
+    fn wake(&self) {
+        let wakers = mem::take(&mut *self.wakers.lock().unwrap());
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+}
+
 /// Handle to adjust the delta of the clock
 #[derive(Clone)]
 pub struct MockHandle {
-    delta: Weak<AtomicI64>,
+    base: Option<SystemTime>,
+    delta: Weak<Delta>,
 }
 
 impl MockHandle {
@@ -46,16 +118,43 @@ impl MockHandle {
             delta = -delta;
         }
 
-        delta_handle.fetch_add(delta, Ordering::AcqRel);
+        delta_handle.nanos.fetch_add(delta, Ordering::AcqRel);
+        delta_handle.wake();
     }
 
     /// Reset the offset to 0
     #[inline]
     pub fn reset(&self) {
         if let Some(delta_handle) = self.delta.upgrade() {
-            delta_handle.store(0, Ordering::Release);
+            delta_handle.nanos.store(0, Ordering::Release);
+            delta_handle.wake();
         }
     }
+
+    /// Set the clock to the specified point in time
+    ///
+    /// For virtual clocks, the clock will stay at this point in time until it is adjusted again.
+    /// Clocks based on the system clock will continue to move forward from this point in time.
+    ///
+    /// # Panics
+    ///
+    /// - The point in time is more than ~292 years away from the base of the clock, which can't be represented as a delta
+    #[inline]
+    pub fn set(&self, time: SystemTime) {
+        let Some(delta_handle) = self.delta.upgrade() else {
+            return;
+        };
+
+        let base = self.base.unwrap_or_else(SystemTime::now);
+        let delta = match time.duration_since(base) {
+            Ok(duration) => i64::try_from(duration.as_nanos()),
+            Err(error) => i64::try_from(error.duration().as_nanos()).map(|nanos| -nanos),
+        };
+        let delta = delta.expect("point in time too far away from the base of the clock");
+
+        delta_handle.nanos.store(delta, Ordering::Release);
+        delta_handle.wake();
+    }
 }
 
 /// Guard which will reset the thread-local upon drop
@@ -70,9 +169,12 @@ impl Drop for ClockGuard {
 }
 
 /// Clock with an optional adjustable delta
+///
+/// The delta is applied either to the system clock or, in case of frozen and virtual clocks, to a fixed point in time
 #[derive(Clone, Default)]
 pub struct Clock {
-    delta: Option<Arc<AtomicI64>>,
+    base: Option<SystemTime>,
+    delta: Option<Arc<Delta>>,
 }
 
 impl Clock {
@@ -89,12 +191,48 @@ impl Clock {
     #[inline]
     #[must_use]
     pub fn mockable() -> (Self, MockHandle) {
-        let delta = Arc::new(AtomicI64::default());
+        let delta = Arc::new(Delta::default());
+
+        let mock_handle = MockHandle {
+            base: None,
+            delta: Arc::downgrade(&delta),
+        };
+        let clock = Self {
+            base: None,
+            delta: Some(delta),
+        };
+
+        (clock, mock_handle)
+    }
+
+    /// Construct a clock which is frozen at the specified point in time
+    ///
+    /// This clock will always return the same time
+    #[inline]
+    #[must_use]
+    pub fn frozen(time: SystemTime) -> Self {
+        Self {
+            base: Some(time),
+            delta: None,
+        }
+    }
+
+    /// Construct a virtual clock starting at the specified point in time
+    ///
+    /// The time of this clock is fully decoupled from the system clock and only moves when it is adjusted via the returned handle
+    #[inline]
+    #[must_use]
+    pub fn virtual_time(start: SystemTime) -> (Self, MockHandle) {
+        let delta = Arc::new(Delta::default());
 
         let mock_handle = MockHandle {
+            base: Some(start),
             delta: Arc::downgrade(&delta),
         };
-        let clock = Self { delta: Some(delta) };
+        let clock = Self {
+            base: Some(start),
+            delta: Some(delta),
+        };
 
         (clock, mock_handle)
     }
@@ -102,6 +240,7 @@ impl Clock {
     /// Enter a context where this clock is installed into the thread-local context
     ///
     /// As long as the guard is kept live, the [`now`] function will read the time of this clock
+    /// (unless a task-local clock is installed, which takes precedence)
     #[inline]
     #[must_use]
     pub fn enter(&self) -> ClockGuard {
@@ -112,14 +251,14 @@ impl Clock {
         }
     }
 
-    /// Read the current time from the system clock and apply the delta
+    /// Read the current time from the system clock (or the fixed point in time of frozen and virtual clocks) and apply the delta
     #[inline]
     #[must_use]
     pub fn now(&self) -> SystemTime {
-        let mut now = SystemTime::now();
+        let mut now = self.base.unwrap_or_else(SystemTime::now);
 
         if let Some(ref delta) = self.delta {
-            let ns_delta = delta.load(Ordering::Acquire);
+            let ns_delta = delta.load();
             if ns_delta.is_positive() {
                 now += Duration::from_nanos(ns_delta as u64);
             } else {
@@ -131,17 +270,36 @@ impl Clock {
     }
 }
 
-/// Read the current time from the thread-local clock
+/// Read the current time from the task-local clock or, if none is installed, the thread-local clock
 #[inline]
 #[must_use]
 pub fn now() -> SystemTime {
+    #[cfg(feature = "tokio")]
+    if let Some(now) = self::task::now() {
+        return now;
+    }
+
     THREAD_CLOCK.with(|clock| clock.borrow().now())
 }
 
+/// Get a clone of the clock currently in effect
+///
+/// This is the task-local clock or, if none is installed, the thread-local clock
+#[inline]
+#[must_use]
+pub fn current() -> Clock {
+    #[cfg(feature = "tokio")]
+    if let Some(clock) = self::task::current() {
+        return clock;
+    }
+
+    THREAD_CLOCK.with(|clock| clock.borrow().clone())
+}
+
 #[cfg(test)]
 mod test {
     use crate::{Clock, DeltaDirection};
-    use std::time::Duration;
+    use std::time::{Duration, SystemTime};
 
     #[test]
     fn can_forward() {
@@ -189,4 +347,182 @@ mod test {
         // but it should pretty much always hold true
         assert_eq!(reset.duration_since(now).unwrap().as_secs(), 0);
     }
+
+    #[test]
+    fn frozen() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+        let clock = Clock::frozen(time);
+        let _clock_guard = clock.enter();
+
+        assert_eq!(crate::now(), time);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(crate::now(), time);
+    }
+
+    #[test]
+    fn virtual_time() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+        let (clock, mock) = Clock::virtual_time(start);
+        let _clock_guard = clock.enter();
+
+        assert_eq!(crate::now(), start);
+
+        mock.adjust(DeltaDirection::Add, Duration::from_secs(10));
+        assert_eq!(crate::now(), start + Duration::from_secs(10));
+
+        mock.adjust(DeltaDirection::Sub, Duration::from_secs(20));
+        assert_eq!(crate::now(), start - Duration::from_secs(10));
+
+        mock.reset();
+        assert_eq!(crate::now(), start);
+    }
+
+    #[test]
+    fn can_set() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+        let (clock, mock) = Clock::virtual_time(start);
+        let _clock_guard = clock.enter();
+
+        let target = SystemTime::UNIX_EPOCH;
+        mock.set(target);
+        assert_eq!(crate::now(), target);
+
+        let (clock, mock) = Clock::mockable();
+        let _clock_guard = clock.enter();
+
+        mock.set(target);
+        assert_eq!(crate::now().duration_since(target).unwrap().as_secs(), 0);
+    }
+
+    #[test]
+    #[should_panic = "too far away"]
+    fn set_out_of_range() {
+        let (_clock, mock) = Clock::virtual_time(SystemTime::UNIX_EPOCH);
+        mock.set(SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 40));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn task_local_follows_task() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+        let (clock, mock) = Clock::virtual_time(start);
+
+        clock
+            .scope(async move {
+                assert_eq!(crate::now(), start);
+
+                for _ in 0..10 {
+                    tokio::task::yield_now().await;
+                    assert_eq!(crate::now(), start);
+                }
+
+                mock.adjust(DeltaDirection::Add, Duration::from_secs(1));
+
+                let spawned = crate::spawn(async { crate::now() }).await.unwrap();
+                assert_eq!(spawned, start + Duration::from_secs(1));
+            })
+            .await;
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn sleep_virtual() {
+        use std::{
+            pin::pin,
+            task::{Context, Waker},
+        };
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_388_957_500);
+        let (clock, mock) = Clock::virtual_time(start);
+        let _clock_guard = clock.enter();
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut sleep = pin!(crate::sleep(Duration::from_secs(10)));
+        assert_eq!(sleep.deadline(), start + Duration::from_secs(10));
+        assert!(sleep.as_mut().poll(&mut cx).is_pending());
+
+        mock.adjust(DeltaDirection::Add, Duration::from_secs(5));
+        assert!(sleep.as_mut().poll(&mut cx).is_pending());
+
+        mock.adjust(DeltaDirection::Add, Duration::from_secs(5));
+        assert!(sleep.as_mut().poll(&mut cx).is_ready());
+
+        let mut sleep = pin!(crate::sleep_until(start));
+        assert!(sleep.as_mut().poll(&mut cx).is_ready());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn sleep_wakers_dont_accumulate() {
+        use std::{
+            sync::Arc,
+            task::{Context, Wake, Waker},
+        };
+
+        struct Noop;
+
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let start = SystemTime::UNIX_EPOCH;
+        let (clock, _mock) = Clock::virtual_time(start);
+        let _clock_guard = clock.enter();
+        let registered = || clock.delta.as_ref().unwrap().wakers.lock().unwrap().len();
+
+        let mut sleep = Box::pin(crate::sleep(Duration::from_secs(10)));
+        for _ in 0..10 {
+            // Distinct wakers, as if the future was moved between tasks
+            let waker = Waker::from(Arc::new(Noop));
+            assert!(
+                sleep
+                    .as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_pending()
+            );
+        }
+        assert_eq!(registered(), 1);
+
+        let mut other = Box::pin(crate::sleep(Duration::from_secs(10)));
+        assert!(
+            other
+                .as_mut()
+                .poll(&mut Context::from_waker(Waker::noop()))
+                .is_pending()
+        );
+        assert_eq!(registered(), 2);
+
+        drop(sleep);
+        drop(other);
+        assert_eq!(registered(), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn sleep_wakes_on_adjust() {
+        let (clock, mock) = Clock::mockable();
+
+        let sleep = {
+            let _clock_guard = clock.enter();
+            tokio::spawn(crate::sleep(Duration::from_hours(1)))
+        };
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!sleep.is_finished());
+
+        mock.adjust(DeltaDirection::Add, Duration::from_hours(1));
+        tokio::time::timeout(Duration::from_secs(5), sleep)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn sleep_real_clock() {
+        let before = crate::now();
+        crate::sleep(Duration::from_millis(10)).await;
+
+        assert!(crate::now().duration_since(before).unwrap() >= Duration::from_millis(10));
+    }
 }
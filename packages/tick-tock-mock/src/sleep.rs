@@ -0,0 +1,109 @@
+//!
+//! Timers driven by the current clock
+//!
+
+use crate::Clock;
+use std::{
+    pin::Pin,
+    task::{self, Poll, ready},
+    time::{Duration, SystemTime},
+};
+
+/// Future returned by [`sleep`] and [`sleep_until`]
+///
+/// Completes once the clock it was created with has passed the deadline:
+///
+/// - unmocked clocks are driven by Tokio's timers
+/// - mockable clocks are driven by Tokio's timers and additionally re-check their deadline whenever they are adjusted
+/// - virtual clocks only complete once they are adjusted past the deadline
+/// - frozen clocks never complete, unless the deadline already lies in the past
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    clock: Clock,
+    deadline: SystemTime,
+    timer: Option<Pin<Box<tokio::time::Sleep>>>,
+
+    /// ID the waker of this future is registered under with the delta of the clock
+    timer_id: u64,
+}
+
+impl Sleep {
+    #[inline]
+    fn new(clock: Clock, deadline: SystemTime) -> Self {
+        let timer_id = clock.delta.as_ref().map_or(0, |delta| delta.timer_id());
+
+        Self {
+            clock,
+            deadline,
+            timer: None,
+            timer_id,
+        }
+    }
+
+    /// Point in time at which this future completes
+    #[inline]
+    #[must_use]
+    pub fn deadline(&self) -> SystemTime {
+        self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        loop {
+            // Register before reading the time so we don't miss an adjustment happening in between
+            if let Some(ref delta) = this.clock.delta {
+                delta.register(this.timer_id, cx.waker());
+            }
+
+            let remaining = match this.deadline.duration_since(this.clock.now()) {
+                Ok(remaining) if !remaining.is_zero() => remaining,
+                _ => return Poll::Ready(()),
+            };
+
+            // Frozen and virtual clocks only move when they are adjusted
+            if this.clock.base.is_some() {
+                return Poll::Pending;
+            }
+
+            let timer_deadline = tokio::time::Instant::now() + remaining;
+            match this.timer {
+                Some(ref mut timer) => timer.as_mut().reset(timer_deadline),
+                None => this.timer = Some(Box::pin(tokio::time::sleep_until(timer_deadline))),
+            }
+
+            ready!(this.timer.as_mut().unwrap().as_mut().poll(cx));
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(ref delta) = self.clock.delta {
+            delta.deregister(self.timer_id);
+        }
+    }
+}
+
+/// Wait until the duration has elapsed on the current clock
+///
+/// The clock is captured when this function is called. See [`Sleep`] for how the different clocks drive the future.
+#[inline]
+pub fn sleep(duration: Duration) -> Sleep {
+    let clock = crate::current();
+    let deadline = clock.now() + duration;
+
+    Sleep::new(clock, deadline)
+}
+
+/// Wait until the current clock has passed the deadline
+///
+/// The clock is captured when this function is called. See [`Sleep`] for how the different clocks drive the future.
+#[inline]
+pub fn sleep_until(deadline: SystemTime) -> Sleep {
+    Sleep::new(crate::current(), deadline)
+}
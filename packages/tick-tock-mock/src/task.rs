@@ -0,0 +1,53 @@
+//!
+//! Task-local clock propagation for Tokio
+//!
+
+use crate::Clock;
+use std::time::SystemTime;
+use tokio::task::{JoinHandle, futures::TaskLocalFuture};
+
+tokio::task_local! {
+    /// Task-local clock
+    ///
+    /// Unlike the thread-local clock, this one follows the future across `.await` points, even if the future migrates threads
+    static TASK_CLOCK: Clock;
+}
+
+/// Read the current time from the task-local clock, if one is installed
+#[inline]
+pub(crate) fn now() -> Option<SystemTime> {
+    TASK_CLOCK.try_with(Clock::now).ok()
+}
+
+/// Get a clone of the task-local clock, if one is installed
+#[inline]
+pub(crate) fn current() -> Option<Clock> {
+    TASK_CLOCK.try_with(Clock::clone).ok()
+}
+
+impl Clock {
+    /// Run the future with this clock installed into the task-local context
+    ///
+    /// For the entire execution of the future, the [`now`](crate::now) function will read the time of this clock.
+    /// Use [`spawn`] to propagate the clock into tasks spawned from inside the future.
+    #[inline]
+    pub fn scope<F>(&self, fut: F) -> TaskLocalFuture<Clock, F>
+    where
+        F: Future,
+    {
+        TASK_CLOCK.scope(self.clone(), fut)
+    }
+}
+
+/// Spawn a new Tokio task which inherits the current clock
+///
+/// This is a drop-in replacement for [`tokio::spawn`]
+#[inline]
+#[track_caller]
+pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(crate::current().scope(fut))
+}
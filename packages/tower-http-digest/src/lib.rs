@@ -148,10 +148,10 @@ where
             .transpose()
             .map_err(Into::into)?
         else {
-            if let Some(verifier) = this.verifier.take() {
-                if !verifier.verify() {
-                    return Poll::Ready(Some(Err("Digest mismatch".into())));
-                }
+            if let Some(verifier) = this.verifier.take()
+                && !verifier.verify()
+            {
+                return Poll::Ready(Some(Err("Digest mismatch".into())));
             }
 
             return Poll::Ready(None);